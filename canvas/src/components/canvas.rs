@@ -2,10 +2,12 @@ use super::macros::{console_log, log};
 use crate::components::elements::get_html_image_element_by_id;
 use crate::components::{elements, ui_element};
 use lazy_static::lazy_static;
+use protocol::Map;
 use std::f64;
 use std::rc::Rc;
 use std::sync::RwLock;
 use wasm_bindgen::prelude::*;
+use web_sys::CanvasRenderingContext2d;
 
 /// Generates the canvas and user interface
 /// # Example
@@ -25,9 +27,42 @@ pub fn initialise_interface() {
     ui_element::pull_tab();
     clear_and_refresh();
 }
-// Global variable to store the rotation angle of the canvas
+// Global variables to store the rotation angle and the map drawn on the canvas
 lazy_static! {
     pub static ref ROTATION_ANGLE: RwLock<f64> = RwLock::new(0.0);
+    pub static ref CURRENT_MAP: RwLock<Map> = RwLock::new(Map::default());
+}
+/// Switch the map drawn behind the players
+/// # Arguments
+/// * `map` - The map named in the latest frame
+/// # Example
+/// ```
+/// set_map(Map::Bind);
+/// ```
+pub fn set_map(map: Map) {
+    let mut current = CURRENT_MAP.write().unwrap();
+    if *current != map {
+        console_log!("Switching map from {} to {}", current.name(), map.name());
+        *current = map;
+    }
+}
+/// Draw the current map on the canvas
+/// # Arguments
+/// * `context` - The canvas context
+/// # Example
+/// ```
+/// draw_map(&context);
+/// ```
+fn draw_map(context: &CanvasRenderingContext2d) {
+    let map = *CURRENT_MAP.read().unwrap();
+    match get_html_image_element_by_id(map.name()) {
+        Ok(image) => {
+            if let Err(err) = context.draw_image_with_html_image_element(&image, 0.0, 0.0) {
+                console_log!("Error drawing image: {:?}", err)
+            };
+        }
+        Err(err) => console_log!("Error getting image: {:?}", err),
+    }
 }
 /// Setter for the rotation angle used to remember the rotation angle
 /// # Arguments
@@ -88,14 +123,29 @@ pub fn clear_and_refresh() {
     context.restore();
     console_log!("Cleared canvas");
 
-    match get_html_image_element_by_id("Ascent") {
-        Ok(image) => {
-            if let Err(err) = context.draw_image_with_html_image_element(&image, 0.0, 0.0) {
-                console_log!("Error drawing image: {:?}", err)
-            };
-        }
-        Err(err) => console_log!("Error getting image: {:?}", err),
-    }
+    draw_map(&context);
+}
+/// Write a notice across the top of the canvas, cleared by the next frame
+/// # Arguments
+/// * `text` - The notice
+/// # Example
+/// ```
+/// draw_notice("Feed stalled");
+/// ```
+pub fn draw_notice(text: &str) {
+    let (_, context, _) = elements::get_canvas_context_document();
+    context.save();
+    if let Err(err) = context.reset_transform() {
+        console_log!("Error resetting transform: {:?}", err)
+    };
+    context.set_fill_style_str("rgba(0, 0, 0, 0.6)");
+    context.fill_rect(0.0, 0.0, 1024.0, 48.0);
+    context.set_font("bold 24px sans-serif");
+    context.set_fill_style_str("white");
+    if let Err(err) = context.fill_text(text, 16.0, 32.0) {
+        console_log!("Error drawing notice: {:?}", err)
+    };
+    context.restore();
 }
 /// Reset the canvas
 /// # Example
@@ -112,14 +162,7 @@ pub fn reset_canvas() {
     context.clear_rect(0.0, 0.0, 1024.0, 1024.0);
     console_log!("Cleared canvas");
 
-    match get_html_image_element_by_id("Ascent") {
-        Ok(image) => {
-            if let Err(err) = context.draw_image_with_html_image_element(&image, 0.0, 0.0) {
-                console_log!("Error drawing image: {:?}", err)
-            };
-        }
-        Err(err) => console_log!("Error getting image: {:?}", err),
-    }
+    draw_map(&context);
 
     change_it(&ROTATION_ANGLE, 0.0);
 }
@@ -3,51 +3,69 @@ use crate::components::elements::{get_canvas_context_document, get_html_image_el
 
 #[derive(Debug)]
 pub struct DeadPlayers {
+    /// The id of the killed player
+    pub id: String,
     pub x: f64,
     pub y: f64,
-    pub prevail_count: i32,
 }
 
 /// Data container for all dead players
 /// # Example
 /// ```
-/// let mut dead_players = DeadPlayers::new(0.0, 0.0);
+/// let mut dead_players = DeadPlayers::new("player-3", 0.0, 0.0);
 /// ```
 impl DeadPlayers {
     /// Create a new DeadPlayers object
     /// # Arguments
+    /// * `id` - The id of the dead player
     /// * `x` - The x coordinate of the dead player
     /// * `y` - The y coordinate of the dead player
     /// # Example
     /// ```
-    /// let mut dead_players = DeadPlayers::new(0.0, 0.0);
+    /// let mut dead_players = DeadPlayers::new("player-3", 0.0, 0.0);
     /// ```
-    pub fn new(x: f64, y: f64) -> Self {
+    pub fn new(id: &str, x: f64, y: f64) -> Self {
         DeadPlayers {
+            id: id.to_string(),
             x,
             y,
-            prevail_count: 5,
         }
     }
-    /// Display dead players as Killed icon on the map for a few seconds
+    /// Mark a player as dead, unless it already is
     /// # Arguments
     /// * `killed` - The vector of killed players
+    /// * `id` - The id of the dead player
+    /// * `x` - The x coordinate where the player died
+    /// * `y` - The y coordinate where the player died
     /// # Example
     /// ```
-    /// DeadPlayers::draw_dead_players(&mut Vec<DeadPlayers>);
+    /// DeadPlayers::mark(&mut dead_players, "player-3", 10.0, 20.0);
     /// ```
-    pub fn draw_dead_players(killed: &mut Vec<DeadPlayers>) {
-        const ALPHA_FACTOR: f64 = 0.2;
+    pub fn mark(killed: &mut Vec<DeadPlayers>, id: &str, x: f64, y: f64) {
+        if !killed.iter().any(|player| player.id == id) {
+            killed.push(DeadPlayers::new(id, x, y));
+        }
+    }
+    /// Display dead players as Killed icon on the map where they fell, until
+    /// they are alive again or the next round starts
+    /// # Arguments
+    /// * `killed` - The vector of killed players
+    /// # Example
+    /// ```
+    /// DeadPlayers::draw_dead_players(&dead_players);
+    /// ```
+    pub fn draw_dead_players(killed: &[DeadPlayers]) {
+        const MARKER_ALPHA: f64 = 0.8;
         const DEATH_ICON_SIZE: f64 = 32.0;
         let (_, context, _) = get_canvas_context_document();
         let angle = get_number(&ROTATION_ANGLE);
-        for player in killed.iter_mut() {
+        for player in killed.iter() {
             context.save();
             context.translate(player.x, player.y).unwrap();
             let angle_rad = get_radian_angle(-angle);
             let death_icon = get_html_image_element_by_id("Killed").unwrap();
             context.rotate(angle_rad).unwrap();
-            context.set_global_alpha(ALPHA_FACTOR * player.prevail_count as f64);
+            context.set_global_alpha(MARKER_ALPHA);
             context
                 .draw_image_with_html_image_element_and_dw_and_dh(
                     &death_icon,
@@ -58,9 +76,6 @@ impl DeadPlayers {
                 )
                 .unwrap();
             context.restore();
-            player.prevail_count -= 1;
         }
-        // Retain the killed players that still have a prevail count
-        killed.retain(|x| x.prevail_count > 0);
     }
 }
@@ -83,10 +83,10 @@ pub fn get_html_image_element_by_id(id: &str) -> Result<HtmlImageElement, ()> {
     };
     match element.dyn_into::<HtmlImageElement>() {
         Ok(img_elem) => Ok(img_elem),
-        Err(_) => Err(console_log!(
-            "Element with id: {} is not an HtmlImageElement",
-            id
-        )),
+        Err(_) => {
+            console_log!("Element with id: {} is not an HtmlImageElement", id);
+            Err(())
+        }
     }
 }
 /// Get the HTML div element by id
@@ -104,10 +104,10 @@ pub fn get_div_element_by_id(id: &str) -> Result<HtmlDivElement, ()> {
     };
     match element.dyn_into::<HtmlDivElement>() {
         Ok(div_elem) => Ok(div_elem),
-        Err(_) => Err(console_log!(
-            "Element with id: {} is not an HtmlDivElement",
-            id
-        )),
+        Err(_) => {
+            console_log!("Element with id: {} is not an HtmlDivElement", id);
+            Err(())
+        }
     }
 }
 /// Get the HTML input element by id
@@ -125,10 +125,10 @@ pub fn get_input_element_by_id(id: &str) -> Result<HtmlInputElement, ()> {
     };
     match element.dyn_into::<HtmlInputElement>() {
         Ok(input_elem) => Ok(input_elem),
-        Err(_) => Err(console_log!(
-            "Element with id: {} is not an HtmlInputElement",
-            id,
-        )),
+        Err(_) => {
+            console_log!("Element with id: {} is not an HtmlInputElement", id);
+            Err(())
+        }
     }
 }
 /// Returns a DivElement by class if it exists
@@ -1,7 +1,9 @@
 use super::macros::{console_log, log};
 use crate::components::elements::{create_html_image_element, get_div_element_by_id};
-use crate::components::player_data::{Agent, Player};
-use crate::components::websocket::get_host;
+use crate::components::player_data::Agent;
+use crate::components::websocket::get_origin;
+use protocol::RoundOutcome;
+pub use protocol::{GameInfo, Map};
 use serde::Deserialize;
 use std::collections::HashMap;
 use strum::IntoEnumIterator;
@@ -9,34 +11,6 @@ use strum_macros::EnumIter;
 use wasm_bindgen::prelude::wasm_bindgen;
 use web_sys::HtmlImageElement;
 
-#[derive(Deserialize, Debug, EnumIter)]
-pub enum Map {
-    Ascent,
-    Bind,
-    Breeze,
-    Haven,
-    Icebox,
-    Split,
-    Lotus,
-    Pearl,
-    Fracture,
-}
-impl Map {
-    pub fn get_string(&self) -> String {
-        match self {
-            Map::Ascent => "Ascent".to_string(),
-            Map::Bind => "Bind".to_string(),
-            Map::Breeze => "Breeze".to_string(),
-            Map::Haven => "Haven".to_string(),
-            Map::Icebox => "Icebox".to_string(),
-            Map::Split => "Split".to_string(),
-            Map::Lotus => "Lotus".to_string(),
-            Map::Pearl => "Pearl".to_string(),
-            Map::Fracture => "Fracture".to_string(),
-        }
-    }
-}
-
 #[derive(Deserialize, Debug, EnumIter)]
 enum Icon {
     Dormant,
@@ -138,43 +112,20 @@ impl Weapon {
 }
 #[derive(Deserialize, Debug, Clone)]
 pub struct GameScore {
-    pub round_win_status: i32,
+    pub round_win_status: RoundOutcome,
 }
 pub fn get_score(score: &[GameScore]) -> (i32, i32) {
     let mut t_score = 0;
     let mut ct_score = 0;
-    for (_, val) in score.iter().enumerate() {
-        if val.round_win_status == 0 {
-            t_score += 1;
-        } else if val.round_win_status == 1 {
-            ct_score += 1;
+    for val in score.iter() {
+        match val.round_win_status {
+            RoundOutcome::Attackers => t_score += 1,
+            RoundOutcome::Defenders => ct_score += 1,
+            RoundOutcome::Unplayed => {}
         }
     }
     (t_score, ct_score)
 }
-#[derive(Deserialize, Debug)]
-pub struct GameInfo {
-    pub round_win_status: Vec<i32>,
-    pub max_rounds: i32,
-    pub round_time: Vec<f64>,
-    pub spike_planted: i32,
-    pub spike_x: Vec<f64>,
-    pub spike_y: Vec<f64>,
-    pub spike_time: Vec<f64>,
-}
-
-impl GameInfo {
-    /// Get the map url
-    /// # Arguments
-    /// * `name` - The name of the map
-    /// # Example
-    /// ```
-    /// assert_eq!(GameInfo::get_map_url("Ascent"), "http://url:8080/images/Ascent.png");
-    /// ```
-    pub fn get_map_url(name: &str) -> String {
-        format!("http://{}/images/{}.png", get_host(), name)
-    }
-}
 #[wasm_bindgen]
 pub struct Preloader {
     agents: HashMap<String, HtmlImageElement>,
@@ -217,14 +168,14 @@ impl Preloader {
         if let Ok(div) = get_div_element_by_id("player_storage") {
             for (id, _player) in Agent::iter().enumerate() {
                 match create_html_image_element(
-                    Player::get_agent_name(id).as_str(),
-                    Player::agent_player_icon_url(id).as_str(),
+                    Agent::get_agent_name(id).as_str(),
+                    Agent::agent_player_icon_url(id).as_str(),
                     class,
                 ) {
                     Ok(element) => {
                         element.style().set_property("display", "none").unwrap();
                         div.append_child(&element).unwrap();
-                        self.agents.insert(Player::get_agent_name(id), element);
+                        self.agents.insert(Agent::get_agent_name(id), element);
                     }
                     Err(err) => console_log!("Error creating image element: {:?}", err),
                 }
@@ -241,16 +192,12 @@ impl Preloader {
     /// ```
     pub fn preload_maps(&mut self, class: &str) {
         if let Ok(div) = get_div_element_by_id("map_storage") {
-            for map_name in Map::iter() {
-                match create_html_image_element(
-                    &map_name.get_string(),
-                    &GameInfo::get_map_url(&map_name.get_string()),
-                    class,
-                ) {
+            for map in Map::ALL {
+                match create_html_image_element(map.name(), get_url(map.name()).as_str(), class) {
                     Ok(element) => {
                         element.style().set_property("display", "none").unwrap();
                         div.append_child(&element).unwrap();
-                        self.maps.insert(map_name.get_string(), element);
+                        self.maps.insert(map.name().to_string(), element);
                     }
                     Err(err) => console_log!("Error creating image element: {:?}", err),
                 }
@@ -317,5 +264,5 @@ impl Default for Preloader {
 }
 
 pub fn get_url(name: &str) -> String {
-    format!("http://{}/images/{}.png", get_host(), name)
+    format!("{}/images/{}.png", get_origin(), name)
 }
@@ -1,7 +1,8 @@
 use crate::components::game_data;
 use crate::components::game_data::{GameInfo, GameScore};
 use crate::components::player::identify_team;
-use wasm_bindgen::{JsCast, JsValue};
+use protocol::Team;
+use wasm_bindgen::JsCast;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
 
 pub struct GameStatus {
@@ -18,8 +19,8 @@ impl GameStatus {
             text_size: 20.0,
             text_colour: "#FFFFFF".to_string(),
             text_font: "sans-serif".to_string(),
-            t_colour: identify_team(0, false).to_string(),
-            ct_colour: identify_team(1, false).to_string(),
+            t_colour: identify_team(Team::Attackers, false).to_string(),
+            ct_colour: identify_team(Team::Defenders, false).to_string(),
         }
     }
     /// Get the canvas context for the game state
@@ -49,18 +50,18 @@ impl GameStatus {
     /// ```
     /// self.create_game_state_row(&info);
     /// ```
-    pub fn create_game_state_row(&self, info: &GameInfo, spike_status: &i32) {
+    pub fn create_game_state_row(&self, info: &GameInfo, spike_status: &bool) {
         let (canvas, _) = self.get_game_state_canvas_context();
         canvas.set_width(300);
         self.add_game_timer(info, spike_status);
     }
 
-    fn add_game_timer(&self, info: &GameInfo, spike_status: &i32) {
+    fn add_game_timer(&self, info: &GameInfo, spike_status: &bool) {
         let (canvas, context) = self.get_game_state_canvas_context();
-        if *spike_status == 1 {
-            context.set_fill_style(&JsValue::from_str("#BC544B"));
+        if *spike_status {
+            context.set_fill_style_str("#BC544B");
         } else {
-            context.set_fill_style(&JsValue::from_str(self.text_colour.as_str()));
+            context.set_fill_style_str(self.text_colour.as_str());
         }
         context.set_font(format!("{}px {}", self.text_size, self.text_font).as_str());
         context.set_text_align("left");
@@ -96,7 +97,7 @@ impl GameStatus {
         let (canvas, context) = self.get_game_state_canvas_context();
         let (t_score, ct_score) = game_data::get_score(info);
         context.set_text_align("center");
-        context.set_fill_style(&JsValue::from_str(self.text_colour.as_str()));
+        context.set_fill_style_str(self.text_colour.as_str());
         context.set_font(format!("{}px {}", self.text_size / 2.0, self.text_font).as_str());
         context
             .fill_text(
@@ -107,7 +108,7 @@ impl GameStatus {
             .unwrap();
         context.set_text_align("center");
         context.set_font(format!("{}px {}", self.text_size * 2.0, self.text_font).as_str());
-        context.set_fill_style(&JsValue::from_str(self.t_colour.as_str()));
+        context.set_fill_style_str(self.t_colour.as_str());
         context
             .fill_text(
                 format!("{}", t_score).as_str(),
@@ -116,7 +117,7 @@ impl GameStatus {
             )
             .unwrap();
         context.set_text_align("center");
-        context.set_fill_style(&JsValue::from_str(self.ct_colour.as_str()));
+        context.set_fill_style_str(self.ct_colour.as_str());
         context
             .fill_text(
                 format!("{}", ct_score).as_str(),
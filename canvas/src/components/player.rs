@@ -1,5 +1,5 @@
 use super::macros::{console_log, log};
-use super::player_data::Player;
+use super::player_data::{Agent, Player};
 use crate::components::canvas::{get_number, get_radian_angle, ROTATION_ANGLE};
 use crate::components::elements::{
     get_canvas_context_document, get_html_image_element_by_id, get_offscreen_canvas_context,
@@ -7,8 +7,8 @@ use crate::components::elements::{
 use crate::components::game_data::Weapon;
 use crate::components::ui_element::{toggle_label, toggle_state};
 use js_sys::Math::{cos, sin};
+use protocol::Team;
 use std::f64;
-use wasm_bindgen::JsValue;
 use web_sys::{HtmlImageElement, OffscreenCanvasRenderingContext2d};
 
 /// Display the player's position on the canvas
@@ -29,7 +29,7 @@ pub fn display_player_position(player: &Player) {
     context
         .arc(player.x, player.y, 10.0, 0.0, f64::consts::PI * 2.0)
         .unwrap();
-    context.set_fill_style(&JsValue::from_str(team_id));
+    context.set_fill_style_str(team_id);
     context.fill();
     player_health_circle(player, get_number(&ROTATION_ANGLE));
 }
@@ -57,7 +57,7 @@ pub fn player_health_circle(player: &Player, angle: f64) {
             calculate_ending_fill_angle(player.health as f64),
         )
         .unwrap();
-    context.set_fill_style(&JsValue::from_str(identify_team(player.team, false)));
+    context.set_fill_style_str(identify_team(player.team, false));
     context.fill();
     context.restore();
 }
@@ -90,7 +90,7 @@ pub fn calculate_ending_fill_angle(health: f64) -> f64 {
 /// * `angle` - The player's angle
 /// # Example
 /// ```
-/// draw_player_labels(0, 100.0, 100.0, 90.0);
+/// draw_player_labels(&[Player], 90.0);
 /// ```
 pub fn draw_player_labels(player: &[Player], angle: f64) {
     let (_, context, _) = get_canvas_context_document();
@@ -98,19 +98,17 @@ pub fn draw_player_labels(player: &[Player], angle: f64) {
     context.set_font("16px sans-serif");
     context.set_text_align("center");
     context.set_text_baseline("middle");
-    context.set_fill_style(&JsValue::from_str("white"));
-    for (_i, player) in player.iter().enumerate() {
+    context.set_fill_style_str("white");
+    for player in player.iter() {
         if angle != 0.0f64 {
             context.save();
             context.translate(player.x, player.y).unwrap();
             let angle_rad = get_radian_angle(-angle);
             context.rotate(angle_rad).unwrap();
-            context.fill_text(&player.id.to_string(), 0.0, 0.0).unwrap();
+            context.fill_text(&player.name, 0.0, 0.0).unwrap();
             context.restore();
         } else {
-            context
-                .fill_text(&player.id.to_string(), player.x, player.y)
-                .unwrap();
+            context.fill_text(&player.name, player.x, player.y).unwrap();
         }
     }
 }
@@ -123,14 +121,14 @@ pub fn draw_player_labels(player: &[Player], angle: f64) {
 /// draw_players(&[Player]);
 /// ```
 pub fn draw_players(players: &[Player]) {
-    for (_i, player) in players.iter().enumerate() {
+    for player in players.iter() {
         if player.health >= 1 {
             draw_player_orientation(player);
             display_player_position(player);
             draw_player_icon(player, get_number(&ROTATION_ANGLE));
             draw_weapon_icons(player, get_number(&ROTATION_ANGLE));
         }
-        if player.defusing == 1 {
+        if player.defusing {
             draw_defusing_icon(player);
         }
     }
@@ -171,7 +169,7 @@ fn draw_defusing_icon(player: &Player) {
         )
         .unwrap();
     context.set_font("12px sans-serif");
-    context.set_fill_style(&JsValue::from_str("#25B14E"));
+    context.set_fill_style_str("#25B14E");
     context
         .fill_text(
             format!("{:.1}", player.defuse_time).as_str(),
@@ -192,7 +190,7 @@ fn draw_defusing_icon(player: &Player) {
 /// ```
 pub fn draw_player_icon(player: &Player, angle: f64) {
     let (_, context, _) = get_canvas_context_document();
-    let agent_name = Player::get_agent_name(player.id as usize);
+    let agent_name = Agent::get_agent_name(player.agent as usize);
     match get_html_image_element_by_id(agent_name.as_str()) {
         Ok(icon) => {
             let icon_width = 16.0;
@@ -206,7 +204,7 @@ pub fn draw_player_icon(player: &Player, angle: f64) {
                 console_log!("Error rotating: {:?}", err);
             }
 
-            if toggle_state("dormant_player_toggle") && player.dormant == 1 {
+            if toggle_state("dormant_player_toggle") && player.dormant {
                 if let Err(err) = context.translate(-icon_width, -icon_height) {
                     console_log!("Error translating: {:?}", err);
                 }
@@ -318,7 +316,7 @@ pub fn set_image_colour(
         .set_global_composite_operation("source-in")
         .expect("Error setting composite operation");
 
-    context.set_fill_style(&JsValue::from_str(colour));
+    context.set_fill_style_str(colour);
     context.fill_rect(x, y, width as f64, height as f64);
     context
         .set_global_composite_operation("source-over")
@@ -350,8 +348,8 @@ fn draw_player_orientation(player: &Player) {
     context.move_to(13.0 * cos(end), 13.0 * sin(end));
     context.line_to(x, y);
 
-    context.set_stroke_style(&JsValue::from_str(identify_team(player.team, false)));
-    context.set_fill_style(&JsValue::from_str(identify_team(player.team, false)));
+    context.set_stroke_style_str(identify_team(player.team, false));
+    context.set_fill_style_str(identify_team(player.team, false));
     context.fill();
 
     context.stroke();
@@ -362,20 +360,13 @@ fn draw_player_orientation(player: &Player) {
 /// * `team` - The player's team
 /// # Example
 /// ```
-/// identify_team(0);
+/// identify_team(Team::Attackers, false);
 /// ```
-pub fn identify_team(team: i32, dark: bool) -> &'static str {
-    if dark {
-        match team {
-            0 => "#66471C",
-            1 => "#212D4C",
-            _ => "grey",
-        }
-    } else {
-        match team {
-            0 => "#DF9B33",
-            1 => "#6678A7",
-            _ => "grey",
-        }
+pub fn identify_team(team: Team, dark: bool) -> &'static str {
+    match (team, dark) {
+        (Team::Attackers, true) => "#66471C",
+        (Team::Defenders, true) => "#212D4C",
+        (Team::Attackers, false) => "#DF9B33",
+        (Team::Defenders, false) => "#6678A7",
     }
 }
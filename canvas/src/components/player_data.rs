@@ -1,4 +1,5 @@
-use crate::components::websocket::get_host;
+use crate::components::websocket::get_origin;
+pub use protocol::Player;
 use serde::Deserialize;
 use strum_macros::EnumIter;
 
@@ -28,55 +29,13 @@ pub enum Agent {
     Deadlock,
 }
 
-/// Data container for a single player
-#[derive(Deserialize, Debug)]
-pub struct Player {
-    pub id: i32,
-    pub x: f64,
-    pub y: f64,
-    pub health: i32,
-    pub team: i32,
-    pub dormant: i32,
-    pub rotation: f64,
-    pub scoped: i32,
-    pub weapon: i32,
-    pub kill: i32,
-    pub death: i32,
-    pub assist: i32,
-    pub acs: i32,
-    pub shield: i32,
-    pub credits: i32,
-    pub defusing: i32,
-    pub defuse_time: f64,
-}
-/// Data container for all players
-#[derive(Deserialize, Debug)]
-pub struct Players {
-    pub id: Vec<i32>,
-    pub x: Vec<f64>,
-    pub y: Vec<f64>,
-    pub health: Vec<i32>,
-    pub team: Vec<i32>,
-    pub dormant: Vec<i32>,
-    pub rotation: Vec<f64>,
-    pub scoped: Vec<i32>,
-    pub weapon: Vec<i32>,
-    pub kill: Vec<i32>,
-    pub death: Vec<i32>,
-    pub assist: Vec<i32>,
-    pub acs: Vec<i32>,
-    pub shield: Vec<i32>,
-    pub credits: Vec<i32>,
-    pub defusing: Vec<i32>,
-    pub defuse_time: Vec<f64>,
-}
-impl Player {
+impl Agent {
     /// Get the agent name from the id
     /// # Arguments
     /// * `id` - The id of the agent
     /// # Example
     /// ```
-    /// assert_eq!(Player::get_agent_name(0), "Brimstone");
+    /// assert_eq!(Agent::get_agent_name(0), "Brimstone");
     /// ```
     pub fn get_agent_name(id: usize) -> String {
         match id {
@@ -110,13 +69,9 @@ impl Player {
     /// * `id` - The id of the agent
     /// # Example
     /// ```
-    /// assert_eq!(Player::agent_player_icon_url(0), "http://url:8080/images/Brimstone.png");
+    /// assert_eq!(Agent::agent_player_icon_url(0), "http://url:8080/images/Brimstone.png");
     /// ```
     pub fn agent_player_icon_url(id: usize) -> String {
-        format!(
-            "http://{}/images/{}.png",
-            get_host(),
-            Player::get_agent_name(id)
-        )
+        format!("{}/images/{}.png", get_origin(), Agent::get_agent_name(id))
     }
 }
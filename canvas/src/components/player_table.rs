@@ -4,8 +4,9 @@ use crate::components::elements::{
 };
 use crate::components::game_data::{get_score, GameScore, Weapon};
 use crate::components::player::identify_team;
-use crate::components::player_data::Player;
-use wasm_bindgen::{JsCast, JsValue};
+use crate::components::player_data::{Agent, Player};
+use protocol::Team;
+use wasm_bindgen::JsCast;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
 
 /// Generates a info table for each player
@@ -35,11 +36,11 @@ fn create_player_info(player: &[Player]) {
         let player_row =
             create_html_div_element(format!("player_{}_info", agent.id).as_str(), "player_row")
                 .unwrap();
-        get_div_element_by_id(format!("team_{}_players", agent.team).as_str())
+        get_div_element_by_id(format!("team_{}_players", agent.team.index()).as_str())
             .unwrap()
             .append_child(&player_row)
             .unwrap();
-        let player_name = Player::get_agent_name(agent.id as usize);
+        let agent_name = Agent::get_agent_name(agent.agent as usize);
 
         let (canvas, context) = new_player_info_block();
         // Set player row layout as three components
@@ -47,7 +48,7 @@ fn create_player_info(player: &[Player]) {
         player_row.append_child(&canvas).unwrap();
         add_health_text_and_bar(&agent, &context, &canvas, health_bar_size);
         add_weapon_icon_to_player_block(agent, &canvas, &context);
-        add_player_name_icon_to_block(&player_name, &canvas, &context, health_bar_size);
+        add_player_name_icon_to_block(&agent_name, &agent.name, &canvas, &context, health_bar_size);
         add_credits_text(
             agent,
             &canvas,
@@ -66,33 +67,29 @@ fn create_player_info(player: &[Player]) {
 /// create_header_info(&team);
 /// ```
 fn create_team_info_header(t_score: i32, ct_score: i32) {
-    for i in 0..2 {
+    for team in Team::ALL {
+        let i = team.index();
         let header =
             create_html_div_element(format!("team_{}_header", i).as_str(), "team_header").unwrap();
         get_div_element_by_id(format!("team_{}_score", i).as_str())
             .unwrap()
             .append_child(&header)
             .unwrap();
-        let team_name = match i {
-            0 => "Attackers",
-            1 => "Defenders",
-            _ => "Unknown",
-        };
-        let score = match i {
-            0 => t_score,
-            1 => ct_score,
-            _ => 0,
+        let team_name = team.name();
+        let score = match team {
+            Team::Attackers => t_score,
+            Team::Defenders => ct_score,
         };
         let (canvas, context) = new_player_info_block();
         header.append_child(&canvas).unwrap();
         context.set_font("bold 18px sans-serif");
         context.set_text_align("left");
         context.set_text_baseline("middle");
-        context.set_fill_style(&JsValue::from_str("white"));
+        context.set_fill_style_str("white");
         context
             .fill_text(team_name, 20.0, canvas.height() as f64 / 2.0)
             .unwrap();
-        context.set_fill_style(&JsValue::from_str(identify_team(i, false)));
+        context.set_fill_style_str(identify_team(team, false));
         context
             .fill_text(
                 score.to_string().as_str(),
@@ -117,7 +114,7 @@ fn add_shield_info(agent: &Player, canvas: &HtmlCanvasElement, context: &CanvasR
     context.set_font("14px sans-serif");
     context.set_text_align("right");
     context.set_text_baseline("middle");
-    context.set_fill_style(&JsValue::from_str("#01FFFE"));
+    context.set_fill_style_str("#01FFFE");
 
     context
         .fill_text(
@@ -157,7 +154,7 @@ fn draw_light_heavy_shield(
     shield_max: f64,
     shield_name: &str,
 ) {
-    context.set_fill_style(&JsValue::from_str("#47FABC"));
+    context.set_fill_style_str("#47FABC");
     context.set_global_alpha(0.5);
     context.fill_rect(
         canvas.width() as f64 - image_size,
@@ -195,7 +192,7 @@ fn add_kda_acs_text(
     context.set_font("14px sans-serif");
     context.set_text_align("left");
     context.set_text_baseline("middle");
-    context.set_fill_style(&JsValue::from_str("white"));
+    context.set_fill_style_str("white");
     context
         .fill_text(
             format!("{} / {} / {}", agent.kill, agent.death, agent.assist).as_str(),
@@ -232,7 +229,7 @@ fn add_credits_text(
     context.set_font("14px sans-serif");
     context.set_text_align("left");
     context.set_text_baseline("middle");
-    context.set_fill_style(&JsValue::from_str("#25B14E"));
+    context.set_fill_style_str("#25B14E");
     context
         .fill_text(
             format!("$ {}", agent.credits.to_string().as_str()).as_str(),
@@ -244,15 +241,17 @@ fn add_credits_text(
 
 /// Adds player name and icon to player info block
 /// # Arguments
+/// * `agent_name` - A string slice of the agent name, used for the icon
 /// * `player_name` - A string slice of the player name
 /// * `canvas` - A canvas element
 /// * `context` - A canvas rendering context
 /// * `health_bar_size` - A f64 value of the health bar size
 /// # Example
 /// ```
-/// add_player_name_icon_to_block(&player_name, canvas, context, health_bar_size);
+/// add_player_name_icon_to_block(&agent_name, &player_name, canvas, context, health_bar_size);
 /// ```
 fn add_player_name_icon_to_block(
+    agent_name: &str,
     player_name: &str,
     canvas: &HtmlCanvasElement,
     context: &CanvasRenderingContext2d,
@@ -260,7 +259,7 @@ fn add_player_name_icon_to_block(
 ) {
     context
         .draw_image_with_html_image_element_and_dw_and_dh(
-            &get_html_image_element_by_id(player_name).unwrap(),
+            &get_html_image_element_by_id(agent_name).unwrap(),
             0.0,
             0.0,
             64.0,
@@ -271,7 +270,7 @@ fn add_player_name_icon_to_block(
     context.set_font("14px sans-serif");
     context.set_text_align("left");
     context.set_text_baseline("middle");
-    context.set_fill_style(&JsValue::from_str("white"));
+    context.set_fill_style_str("white");
     context
         .fill_text(
             player_name,
@@ -327,7 +326,7 @@ fn add_health_text_and_bar(
 ) {
     let bar_height = canvas.height() as f64 / 2.0;
     // Health Bar Background
-    context.set_fill_style(&JsValue::from_str(identify_team(agent.team, true)));
+    context.set_fill_style_str(identify_team(agent.team, true));
     context.fill_rect(
         canvas.width() as f64 - health_bar_size,
         0.0,
@@ -335,7 +334,7 @@ fn add_health_text_and_bar(
         bar_height,
     );
     // Health Bar
-    context.set_fill_style(&JsValue::from_str(identify_team(agent.team, false)));
+    context.set_fill_style_str(identify_team(agent.team, false));
     context.fill_rect(
         canvas.width() as f64 - health_bar_size,
         0.0,
@@ -346,7 +345,7 @@ fn add_health_text_and_bar(
     context.set_font("14px sans-serif");
     context.set_text_align("left");
     context.set_text_baseline("middle");
-    context.set_fill_style(&JsValue::from_str("white"));
+    context.set_fill_style_str("white");
     context
         .fill_text(
             agent.health.to_string().as_str(),
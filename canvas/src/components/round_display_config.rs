@@ -1,7 +1,8 @@
 use crate::components::elements::{get_html_image_element_by_id, get_offscreen_canvas_context};
 use crate::components::game_data::{GameInfo, GameScore};
 use crate::components::player::{identify_team, set_image_colour};
-use wasm_bindgen::{JsCast, JsValue};
+use protocol::RoundOutcome;
+use wasm_bindgen::JsCast;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
 
 #[derive(Debug, Copy, Clone)]
@@ -136,13 +137,17 @@ impl RoundDisplayConfig {
         val: &GameScore,
     ) {
         let (_, context) = self.get_rounds_display_canvas_context();
-        let mut text_colour = identify_team(val.round_win_status, false);
-        if val.round_win_status == 2 && !*present_round {
+        let team_colour = match val.round_win_status.winner() {
+            Some(team) => identify_team(team, false),
+            None => "grey",
+        };
+        let mut text_colour = team_colour;
+        if val.round_win_status == RoundOutcome::Unplayed && !*present_round {
             text_colour = "#BC544B";
             *present_round = true;
         }
         context.set_text_align("center");
-        context.set_fill_style(&JsValue::from_str(text_colour));
+        context.set_fill_style_str(text_colour);
         context.set_font(format!("{}px sans-serif", self.text_size * scaling_factor).as_str());
         context
             .fill_text(
@@ -152,10 +157,7 @@ impl RoundDisplayConfig {
             )
             .unwrap();
         context.begin_path();
-        context.set_stroke_style(&JsValue::from_str(identify_team(
-            val.round_win_status,
-            false,
-        )));
+        context.set_stroke_style_str(team_colour);
         context.rect(
             (*i as f64 * self.gap_size * scaling_factor) - self.rect_size * scaling_factor / 2.0,
             0.0,
@@ -182,7 +184,7 @@ impl RoundDisplayConfig {
         i: f64,
     ) {
         context.set_font(format!("bold {}px sans-serif", self.text_size * scaling_factor).as_str());
-        context.set_fill_style(&JsValue::from_str("white"));
+        context.set_fill_style_str("white");
         context
             .fill_text(
                 format!("OT{}", overtime_count + 1).as_str(),
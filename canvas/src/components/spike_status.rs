@@ -3,7 +3,6 @@ use crate::components::elements::{
     get_canvas_context_document, get_html_image_element_by_id, get_offscreen_canvas_context,
 };
 use crate::components::player::set_image_colour;
-use wasm_bindgen::JsValue;
 
 #[derive(Debug)]
 pub struct SpikeStatus {
@@ -60,7 +59,7 @@ impl SpikeStatus {
             )
             .unwrap();
         context.set_font(format!("{}px Arial", FONT_SIZE).as_str());
-        context.set_fill_style(&JsValue::from_str(image_colour));
+        context.set_fill_style_str(image_colour);
         context
             .fill_text(&format!("{:.1}", self.time), -FONT_SIZE, SPIKE_SIZE / 2.0)
             .unwrap();
@@ -123,20 +123,21 @@ pub fn create_select(name: &str) -> HtmlSelectElement {
 }
 /// Create an option
 /// # Arguments
-/// * `name` - The name of the option
+/// * `value` - The value of the option
+/// * `text` - The text shown for the option
 /// # Example
 /// ```
-/// create_option("name");
+/// create_option("value", "text");
 /// ```
-pub fn create_option(name: &str) -> HtmlOptionElement {
+pub fn create_option(value: &str, text: &str) -> HtmlOptionElement {
     let (_, _, document) = elements::get_canvas_context_document();
     let option = document
         .create_element("option")
         .unwrap()
         .dyn_into::<HtmlOptionElement>()
         .unwrap();
-    option.set_value(name);
-    option.set_text_content(Some(name));
+    option.set_value(value);
+    option.set_text_content(Some(text));
     option
 }
 
@@ -208,7 +209,10 @@ pub fn toggle_orientation(players: &[Player]) {
     if toggle_state("orientation_toggle") {
         let (_, context, _) = elements::get_canvas_context_document();
         let dropdown_value = get_player_dropdown();
-        let rotation_angle = &players[dropdown_value].rotation;
+        let Some(player) = players.iter().find(|player| player.id == dropdown_value) else {
+            return;
+        };
+        let rotation_angle = &player.rotation;
         context.reset_transform().unwrap();
         canvas::rotate_canvas(*rotation_angle);
         canvas::change_it(&ROTATION_ANGLE, *rotation_angle);
@@ -231,14 +235,14 @@ pub fn toggle_state(name: &str) -> bool {
     let toggle_btn = get_input_element_by_id(name).unwrap();
     toggle_btn.checked()
 }
-/// Create the player dropdown
+/// Create the player dropdown, keyed on each player's stable id
 /// # Arguments
-/// * `players` - The number of players in the game
+/// * `players` - The player's data through the struct 'Player' in a vector
 /// # Example
 /// ```
-/// player_dropdown(&usize);
+/// player_dropdown(&[Player]);
 /// ```
-pub fn player_dropdown(players: &usize) {
+pub fn player_dropdown(players: &[Player]) {
     let (_, _, document) = elements::get_canvas_context_document();
     let player_list = document
         .get_element_by_id("player_dropdown")
@@ -247,8 +251,8 @@ pub fn player_dropdown(players: &usize) {
         .unwrap();
     // Clear the dropdown
     player_list.set_inner_html("");
-    for player in 0..*players {
-        let option = create_option(player.to_string().as_str());
+    for player in players.iter() {
+        let option = create_option(&player.id, &player.name);
         player_list.append_child(&option).unwrap();
     }
 }
@@ -258,28 +262,32 @@ pub fn player_dropdown(players: &usize) {
 /// ```
 /// get_player_dropdown();
 /// ```
-pub fn get_player_dropdown() -> usize {
+pub fn get_player_dropdown() -> String {
     let (_, _, document) = elements::get_canvas_context_document();
     let player_dropdown = document
         .get_element_by_id("player_dropdown")
         .unwrap()
         .dyn_into::<HtmlSelectElement>()
         .unwrap();
-    player_dropdown.value().parse::<usize>().unwrap()
+    player_dropdown.value()
 }
-/// Get the player's dropdown length
+/// Get the ids of the players in the dropdown
 /// # Example
 /// ```
-/// get_player_dropdown_length();
+/// get_player_dropdown_ids();
 /// ```
-pub fn get_player_dropdown_length() -> usize {
+pub fn get_player_dropdown_ids() -> Vec<String> {
     let (_, _, document) = elements::get_canvas_context_document();
     let player_dropdown = document
         .get_element_by_id("player_dropdown")
         .unwrap()
         .dyn_into::<HtmlSelectElement>()
         .unwrap();
-    player_dropdown.length() as usize
+    (0..player_dropdown.length())
+        .filter_map(|i| player_dropdown.item(i))
+        .filter_map(|option| option.dyn_into::<HtmlOptionElement>().ok())
+        .map(|option| option.value())
+        .collect()
 }
 pub fn pull_tab() {
     let pull_tab = get_div_element_by_id("pull_tab").unwrap();
@@ -1,7 +1,6 @@
-use super::canvas::clear_and_refresh;
-use super::game_data::GameInfo;
+use super::canvas::{clear_and_refresh, draw_notice, set_map};
 use super::macros::{console_log, log};
-use super::player_data::{Player, Players};
+use super::player_data::Player;
 use crate::components::dead_players::DeadPlayers;
 use crate::components::game_data::GameScore;
 use crate::components::game_status::GameStatus;
@@ -9,20 +8,19 @@ use crate::components::player::draw_players;
 use crate::components::player_table::create_player_info_row;
 use crate::components::round_display_config::RoundDisplayConfig;
 use crate::components::spike_status::SpikeStatus;
-use crate::components::ui_element::{
-    get_player_dropdown_length, player_dropdown, toggle_orientation,
+use crate::components::ui_element::{get_player_dropdown_ids, player_dropdown, toggle_orientation};
+use lazy_static::lazy_static;
+use protocol::{
+    is_valid_room, Control, Data, DeltaDecoder, Encoding, Event, Events, Frame, GameInfo, Hello,
+    HelloReply, Map, Notice, Role, Team, WireMessage, DEFAULT_ROOM,
 };
-use serde::Deserialize;
+use std::fmt::Debug;
+use std::sync::RwLock;
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{ErrorEvent, MessageEvent, WebSocket};
+use web_sys::{BinaryType, ErrorEvent, MessageEvent, UrlSearchParams, WebSocket};
 
-#[derive(Deserialize, Debug)]
-pub struct Data {
-    players: Players,
-    game_info: GameInfo,
-}
 /// A macro to provide `println!(..)`-style syntax for `console.log` logging.
 /// # Example
 /// ```
@@ -35,89 +33,89 @@ pub struct Data {
 /// # Example
 /// ```
 /// use super::websocket::websocket;
-/// let _ws = websocket("ws://localhost:27017");
+/// let _ws = websocket("ws://localhost:27017/match/default");
 /// ```
 #[wasm_bindgen]
 pub fn websocket(url: &str) -> Result<(), JsValue> {
-    // Create WebSocket connection.
-    let ws = WebSocket::new(url)?;
+    // Create WebSocket connection, offering every encoding in order of preference
+    let protocols = js_sys::Array::new();
+    for encoding in Encoding::PREFERRED {
+        protocols.push(&JsValue::from_str(encoding.subprotocol()));
+    }
+    let ws = WebSocket::new_with_str_sequence(url, &protocols)?;
+    ws.set_binary_type(BinaryType::Arraybuffer);
     let mut dead_players: Vec<DeadPlayers> = Vec::new();
+    let mut decoder = DeltaDecoder::new();
 
-    // Listen for incoming test messages
+    // Listen for incoming frames, as JSON text or MessagePack binary
+    let cloned_ws = ws.clone();
     let onmessage_callback = Closure::<dyn FnMut(_)>::new(move |e: MessageEvent| {
-        if let Ok(txt) = e.data().dyn_into::<js_sys::JsString>() {
-            let txt_str = txt.as_string().unwrap();
-            console_log!("message event, received Text");
-            // Process received message
-            match serde_json::from_str::<Data>(&txt_str) {
-                Ok(game_data) => {
-                    let game_info = game_data.game_info;
-                    let mut score: Vec<GameScore> = Vec::new();
-                    for i in 0..game_info.round_win_status.len() {
-                        score.push(GameScore {
-                            round_win_status: game_info.round_win_status[i],
-                        })
+        let frame = decode::<Frame>(&e.data());
+        if frame.is_err() {
+            // Kills are marked where the victim fell until the next round
+            if let Ok(Events { events, .. }) = decode::<Events>(&e.data()) {
+                for event in events {
+                    match event {
+                        Event::Kill { victim, x, y, .. } => {
+                            let (x, y) = match decoder.state() {
+                                Some((_, data)) => to_minimap(&data.game_info, x, y),
+                                None => (x, y),
+                            };
+                            DeadPlayers::mark(&mut dead_players, &victim, x, y);
+                        }
+                        Event::RoundStarted { .. } => dead_players.clear(),
+                        _ => {}
                     }
-
-                    let player_data = game_data.players;
-                    let mut players: Vec<Player> = Vec::new();
-                    // Push the player data into a vector of players
-                    for i in 0..player_data.x.len() {
-                        players.push(Player {
-                            id: player_data.id[i],
-                            x: player_data.x[i],
-                            y: player_data.y[i],
-                            health: player_data.health[i],
-                            team: player_data.team[i],
-                            dormant: player_data.dormant[i],
-                            rotation: player_data.rotation[i],
-                            scoped: player_data.scoped[i],
-                            weapon: player_data.weapon[i],
-                            kill: player_data.kill[i],
-                            death: player_data.death[i],
-                            assist: player_data.assist[i],
-                            acs: player_data.acs[i],
-                            shield: player_data.shield[i],
-                            credits: player_data.credits[i],
-                            defusing: player_data.defusing[i],
-                            defuse_time: player_data.defuse_time[i],
-                        });
-                        if player_data.health[i] < 1 {
-                            dead_players.push(DeadPlayers::new(player_data.x[i], player_data.y[i]))
+                }
+                return;
+            }
+            // The server also tells us when the producers go quiet
+            if let Ok(notice) = decode::<Notice>(&e.data()) {
+                match notice {
+                    Notice::Stalled { silent_for } => {
+                        console_log!("Feed stalled for {}ms", silent_for);
+                        draw_notice("Feed stalled, waiting for the producer");
+                    }
+                    Notice::Resumed => console_log!("Feed resumed"),
+                    Notice::Rejected { error, .. } => {
+                        console_log!("The server refused a message: {}", error)
+                    }
+                }
+                return;
+            }
+            // The only other message the server sends is the reply to our hello
+            if let Ok(reply) = decode::<HelloReply>(&e.data()) {
+                match reply {
+                    HelloReply::Welcome {
+                        version,
+                        encoding,
+                        team,
+                        ..
+                    } => {
+                        console_log!("Joined as viewer, protocol {} ({:?})", version, encoding);
+                        if let Some(team) = team {
+                            console_log!("Showing what team {} sees", team.index());
+                        }
+                        if let Some(fps) = get_frame_rate() {
+                            send_message(&cloned_ws, &Control::SetFrameRate { fps });
                         }
                     }
-                    players.reverse();
-                    clear_and_refresh();
-                    toggle_orientation(&players);
-                    draw_players(&players);
-                    if game_info.spike_planted == 1 {
-                        let spike_status = SpikeStatus::new(
-                            game_info.spike_x[0],
-                            game_info.spike_y[0],
-                            game_info.spike_time[0],
-                        );
-                        spike_status.draw_spike();
-                    };
-                    // Draw dead_players
-                    DeadPlayers::draw_dead_players(&mut dead_players);
-                    create_player_info_row(&players, &score);
-                    // Create the round display
-                    let rounds_display = RoundDisplayConfig::new();
-                    rounds_display.create_rounds_played_row(&score, &game_info);
-                    // Create the game status display
-                    let game_status = GameStatus::new();
-                    game_status.create_game_state_row(&game_info, &game_info.spike_planted);
-                    game_status.add_score_and_round_number(&score);
-                    // Check if current dropdown length is equal to the number of players
-                    if get_player_dropdown_length() != players.len() {
-                        // If not, update the dropdown
-                        player_dropdown(&players.len());
-                    };
+                    HelloReply::Rejected { error } => {
+                        console_log!("Server refused the connection: {}", error)
+                    }
+                }
+                return;
+            }
+        }
+        match frame.map(|frame| decoder.apply(frame)) {
+            Ok(Ok(game_data)) => draw_frame(game_data, &mut dead_players),
+            Ok(Err(err)) => {
+                console_log!("Dropped frame: {}", err);
+                if decoder.take_keyframe_request() {
+                    send_message(&cloned_ws, &Control::RequestKeyframe);
                 }
-                Err(err) => console_log!("Error parsing JSON: {:?}", err),
             }
-        } else {
-            console_log!("message event, received Unknown: {:?}", e.data());
+            Err(err) => console_log!("{}", err),
         }
     });
     // set message event handler on WebSocket
@@ -131,19 +129,18 @@ pub fn websocket(url: &str) -> Result<(), JsValue> {
     ws.set_onerror(Some(onerror_callback.as_ref().unchecked_ref()));
     onerror_callback.forget();
 
-    // Display when the WebSocket has been opened
+    // Introduce ourselves once the WebSocket has been opened
     let cloned_ws = ws.clone();
     let onopen_callback = Closure::<dyn FnMut()>::new(move || {
         console_log!("socket opened");
-        match cloned_ws.send_with_str("ping") {
-            Ok(_) => console_log!("message successfully sent"),
-            Err(err) => console_log!("error sending message: {:?}", err),
-        }
-        // send off binary message
-        match cloned_ws.send_with_u8_array(&[0, 1, 2, 3]) {
-            Ok(_) => console_log!("binary message successfully sent"),
-            Err(err) => console_log!("error sending message: {:?}", err),
+        let mut hello = match get_token() {
+            Some(token) => Hello::new(Role::Viewer).with_token(token),
+            None => Hello::new(Role::Viewer),
+        };
+        if let Some(team) = get_team() {
+            hello = hello.with_team(team);
         }
+        send_message(&cloned_ws, &hello);
     });
     ws.set_onopen(Some(onopen_callback.as_ref().unchecked_ref()));
     onopen_callback.forget();
@@ -151,12 +148,218 @@ pub fn websocket(url: &str) -> Result<(), JsValue> {
     Ok(())
 }
 
-pub fn get_hostname() -> String {
+/// Draw a decoded frame on the canvas and update the player table
+/// # Arguments
+/// * `game_data` - The decoded frame
+/// * `dead_players` - Players killed this round
+/// # Example
+/// ```
+/// draw_frame(&game_data, &mut dead_players);
+/// ```
+fn draw_frame(game_data: &Data, dead_players: &mut Vec<DeadPlayers>) {
+    // Reject malformed frames before anything is drawn
+    if let Err(err) = game_data.validate() {
+        console_log!("Rejected frame: {}", err);
+        return;
+    }
+    let game_info = &game_data.game_info;
+    let mut score: Vec<GameScore> = Vec::new();
+    for i in 0..game_info.round_win_status.len() {
+        score.push(GameScore {
+            round_win_status: game_info.round_win_status[i],
+        })
+    }
+
+    // Unpack the columnar player data into a vector of players, with their
+    // positions projected onto the minimap
+    let mut players: Vec<Player> = game_data.players.to_players();
+    for player in players.iter_mut() {
+        (player.x, player.y) = to_minimap(game_info, player.x, player.y);
+    }
+    // Players dead before the page joined have no kill event, and markers of
+    // players alive again are dropped in case the round start was missed
+    dead_players.retain(|dead| {
+        players
+            .iter()
+            .any(|player| player.id == dead.id && player.health < 1)
+    });
+    for player in players.iter().filter(|player| player.health < 1) {
+        DeadPlayers::mark(dead_players, &player.id, player.x, player.y);
+    }
+    players.reverse();
+    set_map(game_info.map);
+    clear_and_refresh();
+    toggle_orientation(&players);
+    draw_players(&players);
+    if game_info.spike_planted {
+        let (spike_x, spike_y) = to_minimap(game_info, game_info.spike_x[0], game_info.spike_y[0]);
+        let spike_status = SpikeStatus::new(spike_x, spike_y, game_info.spike_time[0]);
+        spike_status.draw_spike();
+    };
+    // Draw dead_players
+    DeadPlayers::draw_dead_players(dead_players);
+    create_player_info_row(&players, &score);
+    // Create the round display
+    let rounds_display = RoundDisplayConfig::new();
+    rounds_display.create_rounds_played_row(&score, game_info);
+    // Create the game status display
+    let game_status = GameStatus::new();
+    game_status.create_game_state_row(game_info, &game_info.spike_planted);
+    game_status.add_score_and_round_number(&score);
+    // Check if the dropdown lists the current players
+    if get_player_dropdown_ids() != game_data.players.id {
+        // If not, update the dropdown
+        player_dropdown(&game_data.players.to_players());
+    };
+}
+
+// The last map found without a calibration, so that it is only logged once
+lazy_static! {
+    static ref UNCALIBRATED_MAP: RwLock<Option<Map>> = RwLock::new(None);
+}
+/// Convert a position from the frame's coordinates to minimap pixels. A map
+/// without a calibration is logged once each time it starts being played
+/// # Arguments
+/// * `game_info` - The game info naming the map and coordinate space
+/// * `x` - The x coordinate
+/// * `y` - The y coordinate
+/// # Example
+/// ```
+/// let (x, y) = to_minimap(&game_info, player.x, player.y);
+/// ```
+fn to_minimap(game_info: &GameInfo, x: f64, y: f64) -> (f64, f64) {
+    let position = game_info.to_minimap(x, y);
+    let mut uncalibrated = UNCALIBRATED_MAP.write().unwrap();
+    match position {
+        Some(position) => {
+            *uncalibrated = None;
+            position
+        }
+        None => {
+            if *uncalibrated != Some(game_info.map) {
+                console_log!(
+                    "No calibration for {}, drawing world coordinates as is",
+                    game_info.map.name()
+                );
+                *uncalibrated = Some(game_info.map);
+            }
+            (x, y)
+        }
+    }
+}
+
+/// Decode a message event, text as JSON and binary as MessagePack
+/// # Arguments
+/// * `data` - The data of the message event
+/// # Example
+/// ```
+/// let frame = decode::<Frame>(&e.data());
+/// ```
+fn decode<T: WireMessage>(data: &JsValue) -> Result<T, String> {
+    if let Some(txt) = data.dyn_ref::<js_sys::JsString>() {
+        let txt_str = String::from(txt);
+        T::from_json(&txt_str).map_err(|err| format!("Error parsing JSON: {:?}", err))
+    } else if let Some(buffer) = data.dyn_ref::<js_sys::ArrayBuffer>() {
+        let bytes = js_sys::Uint8Array::new(buffer).to_vec();
+        T::from_msgpack(&bytes).map_err(|err| format!("Error parsing MessagePack: {:?}", err))
+    } else {
+        Err(format!("message event, received Unknown: {:?}", data))
+    }
+}
+
+/// Send a message to the server in the negotiated encoding
+/// # Arguments
+/// * `ws` - The WebSocket connection
+/// * `message` - The hello or control message
+/// # Example
+/// ```
+/// send_message(&ws, &Control::RequestKeyframe);
+/// ```
+fn send_message<T: WireMessage + Debug>(ws: &WebSocket, message: &T) {
+    let sent = match Encoding::from_subprotocol(&ws.protocol()) {
+        Some(Encoding::MessagePack) => message
+            .to_msgpack()
+            .map(|bytes| ws.send_with_u8_array(&bytes))
+            .map_err(|err| err.to_string()),
+        _ => message
+            .to_json()
+            .map(|text| ws.send_with_str(&text))
+            .map_err(|err| err.to_string()),
+    };
+    match sent {
+        Ok(Ok(_)) => console_log!("message sent: {:?}", message),
+        Ok(Err(err)) => console_log!("error sending message: {:?}", err),
+        Err(err) => console_log!("Error encoding message: {}", err),
+    }
+}
+
+/// The room to watch, chosen with the page's `?match={id}` query parameter
+/// # Example
+/// ```
+/// let room = get_room();
+/// ```
+pub fn get_room() -> String {
     let window = web_sys::window().unwrap();
-    let location = window.location();
-    let hostname = location.hostname().unwrap();
-    console_log!("Hostname: {}", hostname);
-    hostname
+    let search = window.location().search().unwrap_or_default();
+    let room = UrlSearchParams::new_with_str(&search)
+        .ok()
+        .and_then(|params| params.get("match"))
+        .filter(|room| is_valid_room(room))
+        .unwrap_or_else(|| DEFAULT_ROOM.to_string());
+    console_log!("Room: {}", room);
+    room
+}
+
+/// The viewer token, passed with the page's `?token={token}` query parameter
+/// when the server requires one
+pub fn get_token() -> Option<String> {
+    let window = web_sys::window().unwrap();
+    let search = window.location().search().unwrap_or_default();
+    UrlSearchParams::new_with_str(&search)
+        .ok()
+        .and_then(|params| params.get("token"))
+        .filter(|token| !token.is_empty())
+}
+
+/// The team whose view to draw, chosen with the page's `?team={0|1}` query
+/// parameter, e.g. for a coach. The whole game state is drawn when it is
+/// missing
+pub fn get_team() -> Option<Team> {
+    let window = web_sys::window().unwrap();
+    let search = window.location().search().unwrap_or_default();
+    UrlSearchParams::new_with_str(&search)
+        .ok()
+        .and_then(|params| params.get("team"))
+        .and_then(|team| team.parse::<usize>().ok())
+        .and_then(|team| Team::ALL.get(team).copied())
+}
+
+/// The most frames per second to draw, chosen with the page's `?fps={fps}`
+/// query parameter, e.g. for phones and stream overlays. Every frame the
+/// producer sends is drawn when it is missing
+pub fn get_frame_rate() -> Option<u32> {
+    let window = web_sys::window().unwrap();
+    let search = window.location().search().unwrap_or_default();
+    UrlSearchParams::new_with_str(&search)
+        .ok()
+        .and_then(|params| params.get("fps"))
+        .and_then(|fps| fps.parse().ok())
+}
+
+/// Whether the page was served over HTTPS, the relay must then be reached
+/// with `wss://` since browsers block insecure WebSockets on secure pages
+pub fn is_secure() -> bool {
+    let window = web_sys::window().unwrap();
+    window
+        .location()
+        .protocol()
+        .is_ok_and(|protocol| protocol == "https:")
+}
+
+/// The scheme, host and port the page was served from, e.g. `https://url:8080`
+pub fn get_origin() -> String {
+    let window = web_sys::window().unwrap();
+    window.location().origin().unwrap()
 }
 
 pub fn get_host() -> String {
@@ -2,7 +2,8 @@ mod components;
 use crate::components::canvas::initialise_interface;
 use crate::components::game_data::Preloader;
 use crate::components::websocket::websocket;
-use components::websocket::get_hostname;
+use components::websocket::{get_host, get_room, is_secure};
+use protocol::room_path;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen(start)]
@@ -10,5 +11,6 @@ pub fn start() {
     let mut preloader = Preloader::new();
     preloader.preload_assets();
     initialise_interface();
-    let _ws = websocket(format!("ws://{}:27017", get_hostname()).as_str());
+    let scheme = if is_secure() { "wss" } else { "ws" };
+    let _ws = websocket(format!("{}://{}{}", scheme, get_host(), room_path(&get_room())).as_str());
 }
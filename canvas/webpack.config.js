@@ -37,6 +37,15 @@ module.exports = {
           TextEncoder: ['text-encoding', 'TextEncoder']
         })
     ],
+    // The relay serves the WebSocket, forward it when developing with `npm run serve`
+    devServer: {
+        proxy: {
+            '/match': {
+                target: 'ws://localhost:27017',
+                ws: true,
+            },
+        },
+    },
     mode: 'development',
     experiments: {
         asyncWebAssembly: true,
@@ -0,0 +1,273 @@
+use crate::flags::flag;
+use crate::{Coordinates, Data, Frame, GameInfo, Map, Players, RoundOutcome, Team};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Generates a patch struct holding an optional copy of every field of
+/// `$target`, sent only when that field changed. Field attributes are
+/// copied to the patch so that flags keep their wire format
+macro_rules! patch {
+    (
+        $(#[$meta:meta])* $name:ident for $target:ident {
+            $($(#[$field_meta:meta])* $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+        pub struct $name {
+            $(
+                $(#[$field_meta])*
+                #[serde(default, skip_serializing_if = "Option::is_none")]
+                pub $field: Option<$ty>,
+            )*
+        }
+
+        impl $name {
+            /// Collect the fields of `new` that differ from `old`
+            pub fn diff(old: &$target, new: &$target) -> Self {
+                // Fails to compile if a field is missing from the patch
+                let $target { $($field: _),* } = new;
+                $name {
+                    $($field: (old.$field != new.$field).then(|| new.$field.clone()),)*
+                }
+            }
+
+            /// Overwrite the changed fields of `target`
+            pub fn apply(&self, target: &mut $target) {
+                $(
+                    if let Some(value) = &self.$field {
+                        target.$field = value.clone();
+                    }
+                )*
+            }
+
+            /// Take the changed fields of a later patch over the ones of
+            /// this patch
+            pub fn merge(&mut self, later: Self) {
+                $(
+                    if later.$field.is_some() {
+                        self.$field = later.$field;
+                    }
+                )*
+            }
+
+            pub fn is_empty(&self) -> bool {
+                true $(&& self.$field.is_none())*
+            }
+        }
+    };
+}
+
+patch! {
+    /// Player columns that changed, each sent in full
+    PlayersPatch for Players {
+        id: Vec<String>,
+        name: Vec<String>,
+        agent: Vec<i32>,
+        x: Vec<f64>,
+        y: Vec<f64>,
+        health: Vec<i32>,
+        team: Vec<Team>,
+        #[serde(with = "flag")]
+        dormant: Vec<bool>,
+        rotation: Vec<f64>,
+        #[serde(with = "flag")]
+        scoped: Vec<bool>,
+        weapon: Vec<i32>,
+        kill: Vec<i32>,
+        death: Vec<i32>,
+        assist: Vec<i32>,
+        acs: Vec<i32>,
+        shield: Vec<i32>,
+        credits: Vec<i32>,
+        #[serde(with = "flag")]
+        defusing: Vec<bool>,
+        defuse_time: Vec<f64>,
+    }
+}
+
+patch! {
+    /// Game info fields that changed
+    GameInfoPatch for GameInfo {
+        map: Map,
+        coordinates: Coordinates,
+        #[serde(with = "flag")]
+        spike_planted: bool,
+        spike_x: Vec<f64>,
+        spike_y: Vec<f64>,
+        spike_time: Vec<f64>,
+        round_win_status: Vec<RoundOutcome>,
+        played_rounds: i32,
+        max_rounds: i32,
+        round_time: Vec<f64>,
+    }
+}
+
+/// Changes between two consecutive [`Data`] frames
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct DataPatch {
+    #[serde(default, skip_serializing_if = "PlayersPatch::is_empty")]
+    pub players: PlayersPatch,
+    #[serde(default, skip_serializing_if = "GameInfoPatch::is_empty")]
+    pub game_info: GameInfoPatch,
+}
+
+impl DataPatch {
+    pub fn diff(old: &Data, new: &Data) -> Self {
+        DataPatch {
+            players: PlayersPatch::diff(&old.players, &new.players),
+            game_info: GameInfoPatch::diff(&old.game_info, &new.game_info),
+        }
+    }
+
+    pub fn apply(&self, target: &mut Data) {
+        self.players.apply(&mut target.players);
+        self.game_info.apply(&mut target.game_info);
+    }
+
+    /// Combine with the patch of the following frame, the result leads from
+    /// the state before this patch to the state after `later`
+    pub fn merge(&mut self, later: DataPatch) {
+        self.players.merge(later.players);
+        self.game_info.merge(later.game_info);
+    }
+}
+
+/// Turns successive game states into keyframes and deltas on the producer side
+/// # Example
+/// ```
+/// use protocol::{Data, DeltaEncoder};
+/// let mut encoder = DeltaEncoder::new(10);
+/// assert!(encoder.encode(Data::builder().build()).is_keyframe());
+/// assert!(!encoder.encode(Data::builder().build()).is_keyframe());
+/// ```
+#[derive(Debug, Clone)]
+pub struct DeltaEncoder {
+    keyframe_interval: u64,
+    seq: u64,
+    last_keyframe: u64,
+    previous: Option<Data>,
+}
+
+impl DeltaEncoder {
+    /// Create an encoder
+    /// # Arguments
+    /// * `keyframe_interval` - Send a keyframe at least every this many frames
+    pub fn new(keyframe_interval: u64) -> Self {
+        DeltaEncoder {
+            keyframe_interval: keyframe_interval.max(1),
+            seq: 0,
+            last_keyframe: 0,
+            previous: None,
+        }
+    }
+
+    /// Make the next frame a keyframe, e.g. when a viewer reports a gap
+    pub fn request_keyframe(&mut self) {
+        self.previous = None;
+    }
+
+    /// Encode the next game state
+    pub fn encode(&mut self, data: Data) -> Frame {
+        self.seq += 1;
+        let frame = match &self.previous {
+            Some(previous) if self.seq - self.last_keyframe < self.keyframe_interval => {
+                Frame::Delta {
+                    seq: self.seq,
+                    base: None,
+                    patch: DataPatch::diff(previous, &data),
+                }
+            }
+            _ => {
+                self.last_keyframe = self.seq;
+                Frame::Keyframe {
+                    seq: self.seq,
+                    data: data.clone(),
+                }
+            }
+        };
+        self.previous = Some(data);
+        frame
+    }
+}
+
+/// Reason a delta could not be applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaError {
+    /// A delta does not follow the last applied frame
+    SequenceGap { expected: u64, found: u64 },
+    /// A delta arrived before any keyframe
+    MissingKeyframe { found: u64 },
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeltaError::SequenceGap { expected, found } => {
+                write!(f, "expected frame {}, received {}", expected, found)
+            }
+            DeltaError::MissingKeyframe { found } => {
+                write!(f, "received frame {} before a keyframe", found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DeltaError {}
+
+/// Rebuilds the full game state from keyframes and deltas on the viewer side
+#[derive(Debug, Clone, Default)]
+pub struct DeltaDecoder {
+    state: Option<(u64, Data)>,
+    keyframe_requested: bool,
+}
+
+impl DeltaDecoder {
+    pub fn new() -> Self {
+        DeltaDecoder::default()
+    }
+
+    /// The last reconstructed game state and its sequence number
+    pub fn state(&self) -> Option<(u64, &Data)> {
+        self.state.as_ref().map(|(seq, data)| (*seq, data))
+    }
+
+    /// Apply a frame and return the reconstructed game state
+    /// # Returns
+    /// * `Err(DeltaError)` - If a frame was missed, the state is dropped until
+    ///   the next keyframe
+    pub fn apply(&mut self, frame: Frame) -> Result<&Data, DeltaError> {
+        let follows = self.state.as_ref().map(|(last, _)| frame.follows(*last));
+        match frame {
+            Frame::Keyframe { seq, data } => {
+                self.keyframe_requested = false;
+                self.state = Some((seq, data));
+            }
+            Frame::Delta { seq, patch, .. } => match &mut self.state {
+                Some((last, data)) if follows == Some(true) => {
+                    patch.apply(data);
+                    *last = seq;
+                }
+                Some((last, _)) => {
+                    let expected = *last + 1;
+                    self.state = None;
+                    return Err(DeltaError::SequenceGap {
+                        expected,
+                        found: seq,
+                    });
+                }
+                None => return Err(DeltaError::MissingKeyframe { found: seq }),
+            },
+        }
+        Ok(&self.state.as_ref().unwrap().1)
+    }
+
+    /// Whether a keyframe should be requested, true at most once per gap
+    pub fn take_keyframe_request(&mut self) -> bool {
+        if self.state.is_none() && !self.keyframe_requested {
+            self.keyframe_requested = true;
+            return true;
+        }
+        false
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{Control, Data, Events, Frame, Hello, HelloReply, Notice, Record};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Header used to negotiate the [`Encoding`] during the WebSocket handshake
+pub const SUBPROTOCOL_HEADER: &str = "Sec-WebSocket-Protocol";
+
+/// Wire encoding of a frame, negotiated through `Sec-WebSocket-Protocol`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// JSON text messages, used when no subprotocol is offered
+    #[serde(rename = "json")]
+    Json,
+    /// MessagePack binary messages
+    #[serde(rename = "msgpack")]
+    MessagePack,
+}
+
+impl Encoding {
+    /// Encodings in order of preference when negotiating
+    pub const PREFERRED: [Encoding; 2] = [Encoding::MessagePack, Encoding::Json];
+
+    /// The subprotocol name announced for this encoding
+    /// # Example
+    /// ```
+    /// use protocol::Encoding;
+    /// assert_eq!(Encoding::Json.subprotocol(), "valorant-radar.json");
+    /// ```
+    pub fn subprotocol(&self) -> &'static str {
+        match self {
+            Encoding::Json => "valorant-radar.json",
+            Encoding::MessagePack => "valorant-radar.msgpack",
+        }
+    }
+
+    pub fn from_subprotocol(name: &str) -> Option<Encoding> {
+        Encoding::PREFERRED
+            .into_iter()
+            .find(|encoding| encoding.subprotocol() == name.trim())
+    }
+
+    /// Pick the preferred encoding from a `Sec-WebSocket-Protocol` header value
+    /// # Arguments
+    /// * `offered` - Comma separated subprotocols offered by the client
+    /// # Returns
+    /// * `None` - If none of the offered subprotocols are supported
+    /// # Example
+    /// ```
+    /// use protocol::Encoding;
+    /// let offered = "valorant-radar.json, valorant-radar.msgpack";
+    /// assert_eq!(Encoding::negotiate(offered), Some(Encoding::MessagePack));
+    /// assert_eq!(Encoding::negotiate("chat"), None);
+    /// ```
+    pub fn negotiate(offered: &str) -> Option<Encoding> {
+        let offered: Vec<Encoding> = offered
+            .split(',')
+            .filter_map(Encoding::from_subprotocol)
+            .collect();
+        Encoding::PREFERRED
+            .into_iter()
+            .find(|encoding| offered.contains(encoding))
+    }
+
+    /// Whether frames in this encoding are sent as binary messages
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Encoding::MessagePack)
+    }
+}
+
+/// Serialisation shared by every message sent over the socket
+/// # Example
+/// ```
+/// use protocol::{Control, WireMessage};
+/// let text = Control::RequestKeyframe.to_json().unwrap();
+/// assert_eq!(Control::from_json(&text).unwrap(), Control::RequestKeyframe);
+/// ```
+pub trait WireMessage: Serialize + DeserializeOwned {
+    /// Serialise as JSON text
+    fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Parse from JSON text
+    fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+
+    /// Serialise as MessagePack, keeping field names so that fields can be
+    /// added without breaking older readers
+    fn to_msgpack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec_named(self)
+    }
+
+    /// Parse from MessagePack bytes
+    fn from_msgpack(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+}
+
+impl WireMessage for Data {}
+impl WireMessage for Frame {}
+impl WireMessage for Control {}
+impl WireMessage for Notice {}
+impl WireMessage for Hello {}
+impl WireMessage for HelloReply {}
+impl WireMessage for Record {}
+impl WireMessage for Events {}
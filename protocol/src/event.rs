@@ -0,0 +1,163 @@
+use crate::{Data, Player, Team};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Something that happened in a match, found by comparing consecutive game
+/// states. Players are named by their `id`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Event {
+    /// A player's health dropped below 1 at `x`, `y`. `killer` is set when
+    /// exactly one enemy's kill count went up in the same frame
+    Kill {
+        victim: String,
+        killer: Option<String>,
+        x: f64,
+        y: f64,
+    },
+    /// The spike was planted at `x`, `y`
+    Plant {
+        x: f64,
+        y: f64,
+    },
+    DefuseStarted {
+        player: String,
+    },
+    /// A player stopped defusing, because the spike was defused or the
+    /// defuse was interrupted
+    DefuseStopped {
+        player: String,
+    },
+    /// Round `round`, counted from 1, was won by `winner`
+    RoundEnded {
+        round: i32,
+        winner: Team,
+    },
+    /// The teams changed sides, e.g. at half time
+    SidesSwapped,
+    /// The round timer was reset with no spike planted, round `round` began
+    /// its buy phase
+    RoundStarted {
+        round: i32,
+    },
+}
+
+impl Event {
+    /// Find what happened between two game states, in the order it is
+    /// expected to happen within a frame
+    /// # Arguments
+    /// * `previous` - The state before
+    /// * `next` - The state after
+    /// # Returns
+    /// * An empty list if `next` belongs to another match, see
+    ///   [`GameInfo::starts_new_match`](crate::GameInfo::starts_new_match)
+    /// # Example
+    /// ```
+    /// use protocol::{Data, Event, GameInfo};
+    /// let previous = Data::builder().build();
+    /// let next = Data::builder().game_info(GameInfo::builder().spike(10.0, 20.0, 45.0).build()).build();
+    /// assert_eq!(Event::between(&previous, &next), vec![Event::Plant { x: 10.0, y: 20.0 }]);
+    /// ```
+    pub fn between(previous: &Data, next: &Data) -> Vec<Event> {
+        let (before, after) = (&previous.game_info, &next.game_info);
+        if after.starts_new_match(before) {
+            return Vec::new();
+        }
+        let mut events = Vec::new();
+
+        let old_players: HashMap<String, Player> = previous
+            .players
+            .to_players()
+            .into_iter()
+            .map(|player| (player.id.clone(), player))
+            .collect();
+        // Players that were in both states, as they were before and after
+        let players: Vec<(Player, Player)> = next
+            .players
+            .to_players()
+            .into_iter()
+            .filter_map(|player| Some((old_players.get(&player.id)?.clone(), player)))
+            .collect();
+
+        let killers: Vec<&Player> = players
+            .iter()
+            .filter(|(old, new)| new.kill > old.kill)
+            .map(|(_, new)| new)
+            .collect();
+        for (old, new) in &players {
+            if old.health >= 1 && new.health < 1 {
+                let mut enemies = killers.iter().filter(|killer| killer.team != new.team);
+                let killer = match (enemies.next(), enemies.next()) {
+                    (Some(killer), None) => Some(killer.id.clone()),
+                    _ => None,
+                };
+                events.push(Event::Kill {
+                    victim: new.id.clone(),
+                    killer,
+                    x: new.x,
+                    y: new.y,
+                });
+            }
+        }
+
+        if !before.spike_planted && after.spike_planted {
+            if let (Some(x), Some(y)) = (after.spike_x.first(), after.spike_y.first()) {
+                events.push(Event::Plant { x: *x, y: *y });
+            }
+        }
+        for (old, new) in &players {
+            match (old.defusing, new.defusing) {
+                (false, true) => events.push(Event::DefuseStarted {
+                    player: new.id.clone(),
+                }),
+                (true, false) => events.push(Event::DefuseStopped {
+                    player: new.id.clone(),
+                }),
+                _ => {}
+            }
+        }
+
+        for (index, outcome) in after.round_win_status.iter().enumerate() {
+            let was_played = before
+                .round_win_status
+                .get(index)
+                .is_some_and(|outcome| outcome.winner().is_some());
+            if let (Some(winner), false) = (outcome.winner(), was_played) {
+                events.push(Event::RoundEnded {
+                    round: index as i32 + 1,
+                    winner,
+                });
+            }
+        }
+        if !players.is_empty() && players.iter().all(|(old, new)| old.team != new.team) {
+            events.push(Event::SidesSwapped);
+        }
+        let timer_reset = match (before.round_time.first(), after.round_time.first()) {
+            (Some(before), Some(after)) => after > before,
+            _ => false,
+        };
+        if timer_reset && !before.spike_planted && !after.spike_planted {
+            events.push(Event::RoundStarted {
+                round: after.played_rounds + 1,
+            });
+        }
+        events
+    }
+}
+
+/// The events found in a producer's frame, sent by the server to viewers
+/// after the frame itself
+/// # Example
+/// ```
+/// use protocol::{Event, Events, WireMessage};
+/// let events = Events { seq: 12, events: vec![Event::SidesSwapped] };
+/// let text = events.to_json().unwrap();
+/// assert_eq!(text, r#"{"type":"events","seq":12,"events":[{"kind":"sides_swapped"}]}"#);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename = "events")]
+pub struct Events {
+    /// Sequence number of the frame the events were found in
+    pub seq: u64,
+    pub events: Vec<Event>,
+}
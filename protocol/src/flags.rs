@@ -0,0 +1,216 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The side a player is on, sent as `0` for attackers and `1` for defenders
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "i32", into = "i32")]
+pub enum Team {
+    Attackers,
+    Defenders,
+}
+
+impl Team {
+    pub const ALL: [Team; 2] = [Team::Attackers, Team::Defenders];
+
+    /// The index used on the wire and in element ids
+    pub fn index(&self) -> usize {
+        match self {
+            Team::Attackers => 0,
+            Team::Defenders => 1,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Team::Attackers => "Attackers",
+            Team::Defenders => "Defenders",
+        }
+    }
+}
+
+impl TryFrom<i32> for Team {
+    type Error = InvalidValue;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Team::Attackers),
+            1 => Ok(Team::Defenders),
+            _ => Err(InvalidValue {
+                expected: "a team of 0 or 1",
+                found: value,
+            }),
+        }
+    }
+}
+
+impl From<Team> for i32 {
+    fn from(team: Team) -> Self {
+        team.index() as i32
+    }
+}
+
+/// Result of a round, sent as `0` or `1` for the winning team and `2` for
+/// rounds that have not been played yet
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "i32", into = "i32")]
+pub enum RoundOutcome {
+    Attackers,
+    Defenders,
+    Unplayed,
+}
+
+impl RoundOutcome {
+    /// A round won by `team`
+    pub fn won_by(team: Team) -> Self {
+        match team {
+            Team::Attackers => RoundOutcome::Attackers,
+            Team::Defenders => RoundOutcome::Defenders,
+        }
+    }
+
+    /// The team that won the round
+    /// # Returns
+    /// * `None` - If the round has not been played
+    /// # Example
+    /// ```
+    /// use protocol::{RoundOutcome, Team};
+    /// assert_eq!(RoundOutcome::Defenders.winner(), Some(Team::Defenders));
+    /// assert_eq!(RoundOutcome::Unplayed.winner(), None);
+    /// ```
+    pub fn winner(&self) -> Option<Team> {
+        match self {
+            RoundOutcome::Attackers => Some(Team::Attackers),
+            RoundOutcome::Defenders => Some(Team::Defenders),
+            RoundOutcome::Unplayed => None,
+        }
+    }
+}
+
+impl TryFrom<i32> for RoundOutcome {
+    type Error = InvalidValue;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(RoundOutcome::Attackers),
+            1 => Ok(RoundOutcome::Defenders),
+            2 => Ok(RoundOutcome::Unplayed),
+            _ => Err(InvalidValue {
+                expected: "a round outcome of 0, 1 or 2",
+                found: value,
+            }),
+        }
+    }
+}
+
+impl From<RoundOutcome> for i32 {
+    fn from(outcome: RoundOutcome) -> Self {
+        match outcome {
+            RoundOutcome::Attackers => 0,
+            RoundOutcome::Defenders => 1,
+            RoundOutcome::Unplayed => 2,
+        }
+    }
+}
+
+/// An integer on the wire that does not map to a flag or enum variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidValue {
+    pub expected: &'static str,
+    pub found: i32,
+}
+
+impl fmt::Display for InvalidValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for InvalidValue {}
+
+/// A `bool` sent as `0` or `1`
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(try_from = "i32", into = "i32")]
+pub(crate) struct Flag(bool);
+
+impl TryFrom<i32> for Flag {
+    type Error = InvalidValue;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Flag(false)),
+            1 => Ok(Flag(true)),
+            _ => Err(InvalidValue {
+                expected: "a flag of 0 or 1",
+                found: value,
+            }),
+        }
+    }
+}
+
+impl From<Flag> for i32 {
+    fn from(flag: Flag) -> Self {
+        flag.0 as i32
+    }
+}
+
+/// Types made of `bool`s that are sent as integer flags
+pub(crate) trait Flags: Sized {
+    type Repr: Serialize + DeserializeOwned;
+
+    fn to_repr(&self) -> Self::Repr;
+    fn from_repr(repr: Self::Repr) -> Self;
+}
+
+impl Flags for bool {
+    type Repr = Flag;
+
+    fn to_repr(&self) -> Flag {
+        Flag(*self)
+    }
+
+    fn from_repr(repr: Flag) -> Self {
+        repr.0
+    }
+}
+
+impl<T: Flags> Flags for Vec<T> {
+    type Repr = Vec<T::Repr>;
+
+    fn to_repr(&self) -> Self::Repr {
+        self.iter().map(Flags::to_repr).collect()
+    }
+
+    fn from_repr(repr: Self::Repr) -> Self {
+        repr.into_iter().map(T::from_repr).collect()
+    }
+}
+
+impl<T: Flags> Flags for Option<T> {
+    type Repr = Option<T::Repr>;
+
+    fn to_repr(&self) -> Self::Repr {
+        self.as_ref().map(Flags::to_repr)
+    }
+
+    fn from_repr(repr: Self::Repr) -> Self {
+        repr.map(T::from_repr)
+    }
+}
+
+/// Serialise `bool` fields, columns and patches as integer flags with
+/// `#[serde(with = "crate::flags::flag")]`
+pub(crate) mod flag {
+    use super::Flags;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T: Flags, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_repr().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Flags, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        T::Repr::deserialize(deserializer).map(T::from_repr)
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{Data, DataPatch, Event, Frame, Team};
+
+impl Data {
+    /// The state as the players on `side` see it: enemies that are dormant
+    /// keep the position, credits and weapon they were last seen with
+    /// # Arguments
+    /// * `side` - The side the team is on
+    /// * `last_seen` - The state the team saw last, enemies missing from it
+    ///   were never seen and are left out while they stay dormant
+    /// # Example
+    /// ```
+    /// use protocol::{Data, Player, Team};
+    /// let player = |id: &str, team| {
+    ///     let player = Player::builder(id).team(team).dormant(true);
+    ///     player.position(100.0, 100.0).credits(900).build()
+    /// };
+    /// let data = Data::builder()
+    ///     .player(player("ally", Team::Attackers))
+    ///     .player(player("enemy", Team::Defenders))
+    ///     .build();
+    /// let seen = data.seen_by(Team::Attackers, None);
+    /// assert_eq!(seen.players.id, vec!["ally"]);
+    /// assert_eq!(seen.players.credits, vec![900]);
+    /// ```
+    pub fn seen_by(&self, side: Team, last_seen: Option<&Data>) -> Data {
+        let players = self
+            .players
+            .to_players()
+            .into_iter()
+            .filter_map(|mut player| {
+                if player.team == side || !player.dormant {
+                    return Some(player);
+                }
+                let last = last_seen?.players.find(&player.id)?;
+                player.x = last.x;
+                player.y = last.y;
+                player.rotation = last.rotation;
+                player.credits = last.credits;
+                player.weapon = last.weapon;
+                Some(player)
+            });
+        Data {
+            players: players.collect(),
+            game_info: self.game_info.clone(),
+        }
+    }
+}
+
+/// Follows the game state as one team sees it, for viewers that must not
+/// learn what the team cannot, e.g. a coach during a scrim
+/// # Example
+/// ```
+/// use protocol::{Data, Team, TeamView};
+/// let mut view = TeamView::new(Team::Attackers);
+/// let (frame, _) = view.update(1, &Data::builder().build(), true);
+/// assert!(frame.is_keyframe());
+/// let (frame, events) = view.update(2, &Data::builder().build(), false);
+/// assert!(!frame.is_keyframe() && events.is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct TeamView {
+    team: Team,
+    /// Ids of the team's players, to follow the team when sides swap
+    roster: Vec<String>,
+    state: Option<(u64, Data)>,
+}
+
+impl TeamView {
+    /// Create the view of a team
+    /// # Arguments
+    /// * `team` - The side the team is on when the view first sees the
+    ///   match, the team is followed when sides swap
+    pub fn new(team: Team) -> Self {
+        TeamView {
+            team,
+            roster: Vec::new(),
+            state: None,
+        }
+    }
+
+    pub fn team(&self) -> Team {
+        self.team
+    }
+
+    /// The side the team is on in `data`, learning its players when the
+    /// view first sees the match
+    fn side(&mut self, data: &Data, new_match: bool) -> Team {
+        let players = &data.players;
+        let current = |id: &String| {
+            let index = players.id.iter().position(|player| player == id)?;
+            players.team.get(index).copied()
+        };
+        if !new_match {
+            if let Some(side) = self.roster.iter().find_map(current) {
+                return side;
+            }
+        }
+        self.roster = players
+            .id
+            .iter()
+            .zip(&players.team)
+            .filter(|(_, team)| **team == self.team)
+            .map(|(id, _)| id.clone())
+            .collect();
+        self.team
+    }
+
+    /// Filter the next full game state
+    /// # Arguments
+    /// * `seq` - The sequence number of the producer's frame
+    /// * `data` - The full state after the frame
+    /// * `keyframe` - Whether the producer sent a keyframe, the team's
+    ///   viewers are then sent one too
+    /// # Returns
+    /// * `(Frame, Vec<Event>)` - The frame to send the team's viewers and the
+    ///   events they can see in it
+    pub fn update(&mut self, seq: u64, data: &Data, keyframe: bool) -> (Frame, Vec<Event>) {
+        let previous = self.state.take();
+        let new_match = previous
+            .as_ref()
+            .is_some_and(|(_, previous)| data.game_info.starts_new_match(&previous.game_info));
+        let side = self.side(data, new_match);
+        let last_seen = previous
+            .as_ref()
+            .filter(|_| !new_match)
+            .map(|(_, data)| data);
+        let seen = data.seen_by(side, last_seen);
+
+        let events = last_seen.map_or_else(Vec::new, |last_seen| Event::between(last_seen, &seen));
+        let frame = match &previous {
+            Some((last, last_seen)) if !keyframe => Frame::Delta {
+                seq,
+                base: (last + 1 != seq).then_some(*last),
+                patch: DataPatch::diff(last_seen, &seen),
+            },
+            _ => Frame::Keyframe {
+                seq,
+                data: seen.clone(),
+            },
+        };
+        self.state = Some((seq, seen));
+        (frame, events)
+    }
+
+    /// The state the team saw last as a keyframe
+    /// # Returns
+    /// * `None` - Before the first update
+    pub fn keyframe(&self) -> Option<Frame> {
+        self.state.as_ref().map(|(seq, data)| Frame::Keyframe {
+            seq: *seq,
+            data: data.clone(),
+        })
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{DataPatch, FrameError, GameInfo, Player, Players};
+use serde::{Deserialize, Serialize};
+
+/// The full game state for one tick
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Data {
+    pub players: Players,
+    pub game_info: GameInfo,
+}
+
+impl Data {
+    /// Start building a frame
+    /// # Example
+    /// ```
+    /// use protocol::{Data, GameInfo};
+    /// let data = Data::builder().game_info(GameInfo::builder().build()).build();
+    /// assert!(data.players.is_empty());
+    /// ```
+    pub fn builder() -> DataBuilder {
+        DataBuilder::default()
+    }
+}
+
+/// Builder for [`Data`]
+#[derive(Debug, Clone, Default)]
+pub struct DataBuilder {
+    players: Players,
+    game_info: Option<GameInfo>,
+}
+
+impl DataBuilder {
+    /// Append a player to the frame
+    pub fn player(mut self, player: Player) -> Self {
+        self.players.push(player);
+        self
+    }
+
+    /// Append several players to the frame
+    pub fn players<I: IntoIterator<Item = Player>>(mut self, players: I) -> Self {
+        for player in players {
+            self.players.push(player);
+        }
+        self
+    }
+
+    pub fn game_info(mut self, game_info: GameInfo) -> Self {
+        self.game_info = Some(game_info);
+        self
+    }
+
+    /// Build the frame, using an empty `GameInfo` if none was given
+    pub fn build(self) -> Data {
+        Data {
+            players: self.players,
+            game_info: self
+                .game_info
+                .unwrap_or_else(|| GameInfo::builder().build()),
+        }
+    }
+}
+
+/// A message sent by a producer, either the full state or the fields that
+/// changed since the previous frame
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Frame {
+    /// The full game state, sent periodically and on request
+    Keyframe { seq: u64, data: Data },
+    /// Changes relative to the frame with sequence number `base`, or
+    /// `seq - 1` when it is absent. The server sets `base` when it merges
+    /// several deltas for a viewer that asked for a lower frame rate
+    Delta {
+        seq: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base: Option<u64>,
+        patch: DataPatch,
+    },
+}
+
+impl Frame {
+    pub fn seq(&self) -> u64 {
+        match self {
+            Frame::Keyframe { seq, .. } => *seq,
+            Frame::Delta { seq, .. } => *seq,
+        }
+    }
+
+    pub fn is_keyframe(&self) -> bool {
+        matches!(self, Frame::Keyframe { .. })
+    }
+
+    /// Whether the frame applies on top of the state with sequence number
+    /// `last`. Keyframes apply on top of any state
+    pub fn follows(&self, last: u64) -> bool {
+        match self {
+            Frame::Keyframe { .. } => true,
+            Frame::Delta { seq, base, .. } => base.or(seq.checked_sub(1)) == Some(last),
+        }
+    }
+
+    /// Merge a later frame into this one, so that applying the result gives
+    /// the same state as applying both in order
+    /// # Arguments
+    /// * `later` - The frame received after this one. If it does not follow
+    ///   this one it is returned as is, the receiver then reports the gap
+    /// # Example
+    /// ```
+    /// use protocol::{Data, DataPatch, Frame};
+    /// let first = Frame::Delta { seq: 2, base: None, patch: DataPatch::default() };
+    /// let second = Frame::Delta { seq: 3, base: None, patch: DataPatch::default() };
+    /// assert!(first.coalesce(second).follows(1));
+    /// ```
+    pub fn coalesce(self, later: Frame) -> Frame {
+        if !later.follows(self.seq()) {
+            return later;
+        }
+        match (self, later) {
+            (_, later @ Frame::Keyframe { .. }) => later,
+            (Frame::Keyframe { mut data, .. }, Frame::Delta { seq, patch, .. }) => {
+                patch.apply(&mut data);
+                Frame::Keyframe { seq, data }
+            }
+            (
+                Frame::Delta {
+                    seq: first,
+                    base,
+                    mut patch,
+                },
+                Frame::Delta {
+                    seq, patch: later, ..
+                },
+            ) => {
+                patch.merge(later);
+                Frame::Delta {
+                    seq,
+                    base: base.or(first.checked_sub(1)),
+                    patch,
+                }
+            }
+        }
+    }
+}
+
+/// A message sent by a viewer back to the producers
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Control {
+    /// Ask producers to send a keyframe, e.g. after a sequence gap
+    RequestKeyframe,
+    /// Pause a replay, ignored by live producers
+    Pause,
+    /// Resume a paused replay
+    Resume,
+    /// Jump to a position in a replay, in milliseconds from its start
+    Seek { position: u64 },
+    /// Ask the server to send this viewer at most `fps` frames per second,
+    /// merging the frames in between. `0` sends every frame. Handled by the
+    /// server, producers never receive it
+    SetFrameRate { fps: u32 },
+}
+
+/// A message sent by the server to viewers about the state of the feed, or
+/// to a peer whose message it refused
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notice {
+    /// No frame was received from the producers for `silent_for` milliseconds
+    Stalled { silent_for: u64 },
+    /// Frames are arriving again after a stall
+    Resumed,
+    /// The peer's message was not relayed. `seq` is the sequence number of
+    /// the refused frame, if it could be read
+    Rejected {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seq: Option<u64>,
+        error: FrameError,
+    },
+}
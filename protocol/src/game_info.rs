@@ -0,0 +1,156 @@
+use crate::flags::flag;
+use crate::{Coordinates, Map, RoundOutcome};
+use serde::{Deserialize, Serialize};
+
+/// Round and spike state for the current game
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GameInfo {
+    /// Producers that do not send a map are assumed to be on Ascent
+    #[serde(default)]
+    pub map: Map,
+    /// Space of the player and spike positions, minimap pixels unless the
+    /// producer says otherwise
+    #[serde(default)]
+    pub coordinates: Coordinates,
+    #[serde(with = "flag")]
+    pub spike_planted: bool,
+    pub spike_x: Vec<f64>,
+    pub spike_y: Vec<f64>,
+    pub spike_time: Vec<f64>,
+    pub round_win_status: Vec<RoundOutcome>,
+    pub played_rounds: i32,
+    pub max_rounds: i32,
+    pub round_time: Vec<f64>,
+}
+
+impl GameInfo {
+    /// Convert a player or spike position to minimap pixels
+    /// # Returns
+    /// * `None` - If the position is in world units on a map without a
+    ///   [`Calibration`](crate::Calibration)
+    /// # Example
+    /// ```
+    /// use protocol::GameInfo;
+    /// let info = GameInfo::builder().build();
+    /// assert_eq!(info.to_minimap(512.0, 256.0), Some((512.0, 256.0)));
+    /// ```
+    pub fn to_minimap(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        match self.coordinates {
+            Coordinates::Minimap => Some((x, y)),
+            Coordinates::World => Some(self.map.calibration()?.to_minimap(x, y)),
+        }
+    }
+
+    /// Whether this state belongs to a different match than `previous`: the
+    /// map changed or the round count went back
+    /// # Example
+    /// ```
+    /// use protocol::{GameInfo, Map};
+    /// let previous = GameInfo::builder().map(Map::Bind).played_rounds(12).build();
+    /// let next = GameInfo::builder().map(Map::Bind).played_rounds(13).build();
+    /// assert!(!next.starts_new_match(&previous));
+    /// let next = GameInfo::builder().map(Map::Bind).build();
+    /// assert!(next.starts_new_match(&previous));
+    /// ```
+    pub fn starts_new_match(&self, previous: &GameInfo) -> bool {
+        self.map != previous.map || self.played_rounds < previous.played_rounds
+    }
+
+    /// Start building a `GameInfo` with no rounds played and no spike planted
+    /// # Example
+    /// ```
+    /// use protocol::GameInfo;
+    /// let info = GameInfo::builder().round_time(100.0).build();
+    /// assert_eq!(info.round_time, vec![100.0]);
+    /// ```
+    pub fn builder() -> GameInfoBuilder {
+        GameInfoBuilder::default()
+    }
+}
+
+/// Builder for [`GameInfo`]
+#[derive(Debug, Clone)]
+pub struct GameInfoBuilder {
+    map: Map,
+    coordinates: Coordinates,
+    spike: Option<(f64, f64, f64)>,
+    round_win_status: Vec<RoundOutcome>,
+    played_rounds: i32,
+    max_rounds: i32,
+    round_time: f64,
+}
+
+impl Default for GameInfoBuilder {
+    fn default() -> Self {
+        GameInfoBuilder {
+            map: Map::default(),
+            coordinates: Coordinates::default(),
+            spike: None,
+            round_win_status: Vec::new(),
+            played_rounds: 0,
+            max_rounds: 24,
+            round_time: 0.0,
+        }
+    }
+}
+
+impl GameInfoBuilder {
+    pub fn map(mut self, map: Map) -> Self {
+        self.map = map;
+        self
+    }
+
+    pub fn coordinates(mut self, coordinates: Coordinates) -> Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Mark the spike as planted
+    /// # Arguments
+    /// * `x` - The x coordinate of the spike
+    /// * `y` - The y coordinate of the spike
+    /// * `time` - The time left on the spike
+    pub fn spike(mut self, x: f64, y: f64, time: f64) -> Self {
+        self.spike = Some((x, y, time));
+        self
+    }
+
+    pub fn round_win_status(mut self, round_win_status: Vec<RoundOutcome>) -> Self {
+        self.round_win_status = round_win_status;
+        self
+    }
+
+    pub fn played_rounds(mut self, played_rounds: i32) -> Self {
+        self.played_rounds = played_rounds;
+        self
+    }
+
+    pub fn max_rounds(mut self, max_rounds: i32) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    pub fn round_time(mut self, round_time: f64) -> Self {
+        self.round_time = round_time;
+        self
+    }
+
+    pub fn build(self) -> GameInfo {
+        let (spike_planted, (spike_x, spike_y, spike_time)) = match self.spike {
+            Some(spike) => (true, spike),
+            None => (false, (0.0, 0.0, 0.0)),
+        };
+        GameInfo {
+            map: self.map,
+            coordinates: self.coordinates,
+            spike_planted,
+            spike_x: vec![spike_x],
+            spike_y: vec![spike_y],
+            spike_time: vec![spike_time],
+            round_win_status: self.round_win_status,
+            played_rounds: self.played_rounds,
+            max_rounds: self.max_rounds,
+            round_time: vec![self.round_time],
+        }
+    }
+}
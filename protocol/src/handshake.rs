@@ -0,0 +1,192 @@
+use crate::{Encoding, Team};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Version of the message format, bumped on breaking changes
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// What a peer does on the relay
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Sends game frames
+    Producer,
+    /// Receives game frames and may request keyframes
+    Viewer,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Producer => write!(f, "producer"),
+            Role::Viewer => write!(f, "viewer"),
+        }
+    }
+}
+
+/// First message a peer sends after the socket opens
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename = "hello")]
+pub struct Hello {
+    pub role: Role,
+    pub version: u32,
+    /// Encodings the peer can read, in order of preference
+    pub encodings: Vec<Encoding>,
+    /// Secret proving the peer may join in its role
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// The team whose view a viewer asks for, see [`TeamView`](crate::TeamView).
+    /// Viewers without a team observe the full game state
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<Team>,
+}
+
+impl Hello {
+    /// A hello for this version of the protocol, supporting every encoding
+    /// # Example
+    /// ```
+    /// use protocol::{Hello, Role, PROTOCOL_VERSION};
+    /// let hello = Hello::new(Role::Viewer);
+    /// assert_eq!(hello.version, PROTOCOL_VERSION);
+    /// ```
+    pub fn new(role: Role) -> Self {
+        Hello {
+            role,
+            version: PROTOCOL_VERSION,
+            encodings: Encoding::PREFERRED.to_vec(),
+            token: None,
+            team: None,
+        }
+    }
+
+    /// Authenticate with a token
+    /// # Example
+    /// ```
+    /// use protocol::{Hello, Role};
+    /// let hello = Hello::new(Role::Producer).with_token("secret");
+    /// assert_eq!(hello.token.as_deref(), Some("secret"));
+    /// ```
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Watch as one team sees the game, enemies it cannot see are hidden
+    /// # Example
+    /// ```
+    /// use protocol::{Hello, Role, Team, WireMessage};
+    /// let hello = Hello::new(Role::Viewer).with_team(Team::Defenders);
+    /// assert!(hello.to_json().unwrap().contains(r#""team":1"#));
+    /// ```
+    pub fn with_team(mut self, team: Team) -> Self {
+        self.team = Some(team);
+        self
+    }
+
+    /// Check the hello against this version of the protocol
+    /// # Arguments
+    /// * `negotiated` - The encoding picked from `Sec-WebSocket-Protocol`,
+    ///   used if the peer supports it
+    /// # Returns
+    /// * `Ok(Encoding)` - The encoding frames will be sent in
+    /// # Example
+    /// ```
+    /// use protocol::{Encoding, Hello, Role};
+    /// let hello = Hello::new(Role::Producer);
+    /// assert_eq!(hello.accept(Encoding::Json), Ok(Encoding::Json));
+    /// ```
+    pub fn accept(&self, negotiated: Encoding) -> Result<Encoding, HandshakeError> {
+        if self.version != PROTOCOL_VERSION {
+            return Err(HandshakeError::UnsupportedVersion {
+                requested: self.version,
+                supported: PROTOCOL_VERSION,
+            });
+        }
+        if self.encodings.contains(&negotiated) {
+            return Ok(negotiated);
+        }
+        Encoding::PREFERRED
+            .into_iter()
+            .find(|encoding| self.encodings.contains(encoding))
+            .ok_or(HandshakeError::NoCommonEncoding)
+    }
+}
+
+/// Server reply to a [`Hello`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HelloReply {
+    /// The peer was accepted with these options
+    Welcome {
+        version: u32,
+        role: Role,
+        encoding: Encoding,
+        /// The team whose view the viewer is sent, `None` for the full state
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        team: Option<Team>,
+    },
+    /// The peer was refused and the socket will be closed
+    Rejected { error: HandshakeError },
+}
+
+/// Reason a [`Hello`] was refused
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum HandshakeError {
+    /// The first message was not a hello
+    ExpectedHello,
+    /// The peer speaks a different protocol version
+    UnsupportedVersion { requested: u32, supported: u32 },
+    /// None of the peer's encodings are supported
+    NoCommonEncoding,
+    /// The token is missing or not valid for the role
+    Unauthorized { role: Role },
+    /// The room already holds as many peers in the role as the server allows
+    RoomFull { role: Role },
+    /// The room is serving a recorded match and takes no producers
+    Replaying,
+}
+
+impl HandshakeError {
+    /// WebSocket close code sent after the rejection: `4401` for a missing or
+    /// invalid token, `1013` (try again later) for a full room, `1008`
+    /// (policy violation) otherwise
+    pub fn close_code(&self) -> u16 {
+        match self {
+            HandshakeError::Unauthorized { .. } => 4401,
+            HandshakeError::RoomFull { .. } => 1013,
+            _ => 1008,
+        }
+    }
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandshakeError::ExpectedHello => write!(f, "expected a hello message"),
+            HandshakeError::UnsupportedVersion {
+                requested,
+                supported,
+            } => write!(
+                f,
+                "protocol version {} is not supported, the server speaks version {}",
+                requested, supported
+            ),
+            HandshakeError::NoCommonEncoding => write!(f, "no supported encoding was offered"),
+            HandshakeError::Unauthorized { role } => {
+                write!(f, "a valid token is required to join as a {}", role)
+            }
+            HandshakeError::RoomFull { role } => {
+                write!(f, "the room cannot take another {}", role)
+            }
+            HandshakeError::Replaying => {
+                write!(
+                    f,
+                    "the room is replaying a recording and takes no producers"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for HandshakeError {}
@@ -0,0 +1,50 @@
+//! Wire format shared by the relay server, the test-client and the canvas.
+//!
+//! A producer sends one [`Frame`] per tick: a [`Data`] keyframe holding the
+//! full game state, or a [`DataPatch`] holding only the fields that changed.
+//! The player list is sent in a columnar layout ([`Players`]) and can be
+//! converted to and from rows of [`Player`]. [`GameInfo`] names the [`Map`]
+//! being played and whether positions are minimap pixels or world units.
+//! The integer flags of the wire format are parsed into [`Team`],
+//! [`RoundOutcome`] and `bool`s, so invalid values are parse errors. Game
+//! states should be checked with [`Data::validate`] before use.
+//!
+//! Messages are sent as JSON text or MessagePack binary messages, see
+//! [`Encoding`] and [`WireMessage`]. Every peer opens with a [`Hello`] naming
+//! its [`Role`] and protocol version, answered by a [`HelloReply`]. The server
+//! tells viewers when the feed stalls with a [`Notice`], and peers whose
+//! message it refused with a [`FrameError`]. What happened in each frame,
+//! e.g. kills and plants, is derived with [`Event::between`] and sent to
+//! viewers as [`Events`]. Viewers of one team are sent its [`TeamView`],
+//! hiding the enemies it cannot see. Peers join the room named by their
+//! request path, see [`room_path`]. Recorded matches are stored as one
+//! [`Record`] per line.
+mod delta;
+mod encoding;
+mod event;
+mod flags;
+mod fog;
+mod frame;
+mod game_info;
+mod handshake;
+mod map;
+mod player;
+mod record;
+mod room;
+mod validate;
+
+pub use delta::{DataPatch, DeltaDecoder, DeltaEncoder, DeltaError, GameInfoPatch, PlayersPatch};
+pub use encoding::{Encoding, WireMessage, SUBPROTOCOL_HEADER};
+pub use event::{Event, Events};
+pub use flags::{InvalidValue, RoundOutcome, Team};
+pub use fog::TeamView;
+pub use frame::{Control, Data, DataBuilder, Frame, Notice};
+pub use game_info::{GameInfo, GameInfoBuilder};
+pub use handshake::{HandshakeError, Hello, HelloReply, Role, PROTOCOL_VERSION};
+pub use map::{Calibration, Coordinates, Map, MINIMAP_SIZE};
+pub use player::{Player, PlayerBuilder, Players};
+pub use record::Record;
+pub use room::{is_valid_room, room_from_path, room_path, DEFAULT_ROOM, MAX_ROOM_LEN};
+pub use validate::{
+    FrameError, ValidationError, AGENT_COUNT, MAX_HEALTH, MAX_SHIELD, WEAPON_COUNT,
+};
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+/// Width and height of every minimap image, in pixels
+pub const MINIMAP_SIZE: f64 = 1024.0;
+
+/// Map being played, sent by name, e.g. `"Ascent"`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Map {
+    #[default]
+    Ascent,
+    Bind,
+    Breeze,
+    District,
+    Fracture,
+    Haven,
+    Icebox,
+    Kasbah,
+    Lotus,
+    Pearl,
+    Piazza,
+    Range,
+    Split,
+}
+
+impl Map {
+    /// Every map with a minimap image
+    pub const ALL: [Map; 13] = [
+        Map::Ascent,
+        Map::Bind,
+        Map::Breeze,
+        Map::District,
+        Map::Fracture,
+        Map::Haven,
+        Map::Icebox,
+        Map::Kasbah,
+        Map::Lotus,
+        Map::Pearl,
+        Map::Piazza,
+        Map::Range,
+        Map::Split,
+    ];
+
+    /// The name of the map, also used as the name of its minimap image
+    /// # Example
+    /// ```
+    /// use protocol::Map;
+    /// assert_eq!(Map::Kasbah.name(), "Kasbah");
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            Map::Ascent => "Ascent",
+            Map::Bind => "Bind",
+            Map::Breeze => "Breeze",
+            Map::District => "District",
+            Map::Fracture => "Fracture",
+            Map::Haven => "Haven",
+            Map::Icebox => "Icebox",
+            Map::Kasbah => "Kasbah",
+            Map::Lotus => "Lotus",
+            Map::Pearl => "Pearl",
+            Map::Piazza => "Piazza",
+            Map::Range => "Range",
+            Map::Split => "Split",
+        }
+    }
+
+    /// How world coordinates on this map line up with its minimap image.
+    /// Values match the multipliers and offsets published with the map
+    /// images on valorant-api.com
+    /// # Returns
+    /// * `None` - If the map has not been calibrated, which is the case for
+    ///   District, Kasbah, Piazza and Range
+    pub fn calibration(&self) -> Option<Calibration> {
+        let (multiplier, x_offset, y_offset) = match self {
+            Map::Ascent => (0.00007, 0.813895, 0.573242),
+            Map::Bind => (0.000059, 0.576941, 0.967566),
+            Map::Breeze => (0.00007, 0.465123, 0.833078),
+            Map::Fracture => (0.000078, 0.556952, 1.155886),
+            Map::Haven => (0.000075, 1.09345, 0.642728),
+            Map::Icebox => (0.000072, 0.460214, 0.304687),
+            Map::Lotus => (0.000072, 0.454789, 0.917752),
+            Map::Pearl => (0.000078, 0.480469, 0.916016),
+            Map::Split => (0.000078, 0.842188, 0.697578),
+            Map::District | Map::Kasbah | Map::Piazza | Map::Range => return None,
+        };
+        // The game's y axis runs along the minimap's x axis
+        Some(Calibration {
+            x_multiplier: multiplier,
+            y_multiplier: -multiplier,
+            x_offset,
+            y_offset,
+            swap_axes: true,
+        })
+    }
+}
+
+/// Space the player and spike positions of a frame are given in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Coordinates {
+    /// Pixels on the 1024px minimap image
+    #[default]
+    Minimap,
+    /// Game world units, projected with the map's [`Calibration`]
+    World,
+}
+
+/// Linear mapping from world units to the minimap, as a fraction of its size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub x_multiplier: f64,
+    pub y_multiplier: f64,
+    pub x_offset: f64,
+    pub y_offset: f64,
+    /// Whether the world x axis runs along the minimap's y axis
+    pub swap_axes: bool,
+}
+
+impl Calibration {
+    /// Project a world position onto the minimap
+    /// # Arguments
+    /// * `x` - The world x coordinate
+    /// * `y` - The world y coordinate
+    /// # Returns
+    /// * `(f64, f64)` - The position in minimap pixels
+    /// # Example
+    /// ```
+    /// use protocol::{Map, MINIMAP_SIZE};
+    /// let calibration = Map::Ascent.calibration().unwrap();
+    /// let (x, y) = calibration.to_minimap(0.0, 0.0);
+    /// assert_eq!((x, y), (0.813895 * MINIMAP_SIZE, 0.573242 * MINIMAP_SIZE));
+    /// ```
+    pub fn to_minimap(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = if self.swap_axes { (y, x) } else { (x, y) };
+        (
+            (x * self.x_multiplier + self.x_offset) * MINIMAP_SIZE,
+            (y * self.y_multiplier + self.y_offset) * MINIMAP_SIZE,
+        )
+    }
+}
@@ -0,0 +1,294 @@
+use crate::flags::flag;
+use crate::Team;
+use serde::{Deserialize, Serialize};
+
+/// Data container for a single player
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Player {
+    /// Identifies the player for the whole match, unlike the agent
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Agent index, see `AGENT_COUNT`
+    pub agent: i32,
+    pub x: f64,
+    pub y: f64,
+    pub health: i32,
+    pub team: Team,
+    #[serde(with = "flag")]
+    pub dormant: bool,
+    pub rotation: f64,
+    #[serde(with = "flag")]
+    pub scoped: bool,
+    pub weapon: i32,
+    pub kill: i32,
+    pub death: i32,
+    pub assist: i32,
+    pub acs: i32,
+    pub shield: i32,
+    pub credits: i32,
+    #[serde(with = "flag")]
+    pub defusing: bool,
+    pub defuse_time: f64,
+}
+
+impl Player {
+    /// Start building a player on the attacking side, alive with full health,
+    /// at `0, 0` and with no credits
+    /// # Arguments
+    /// * `id` - The stable identifier of the player, also used as its name
+    /// # Example
+    /// ```
+    /// use protocol::{Player, Team};
+    /// let player = Player::builder("p1").team(Team::Defenders).position(10.0, 20.0).build();
+    /// assert_eq!((player.x, player.y, player.health), (10.0, 20.0, 100));
+    /// ```
+    pub fn builder(id: &str) -> PlayerBuilder {
+        PlayerBuilder {
+            player: Player {
+                id: id.to_string(),
+                name: id.to_string(),
+                agent: 0,
+                x: 0.0,
+                y: 0.0,
+                health: 100,
+                team: Team::Attackers,
+                dormant: false,
+                rotation: 0.0,
+                scoped: false,
+                weapon: 0,
+                kill: 0,
+                death: 0,
+                assist: 0,
+                acs: 0,
+                shield: 0,
+                credits: 0,
+                defusing: false,
+                defuse_time: -1.0,
+            },
+        }
+    }
+}
+
+/// Builder for [`Player`]
+#[derive(Debug, Clone)]
+pub struct PlayerBuilder {
+    player: Player,
+}
+
+impl PlayerBuilder {
+    pub fn name(mut self, name: &str) -> Self {
+        self.player.name = name.to_string();
+        self
+    }
+
+    pub fn agent(mut self, agent: i32) -> Self {
+        self.player.agent = agent;
+        self
+    }
+
+    pub fn position(mut self, x: f64, y: f64) -> Self {
+        self.player.x = x;
+        self.player.y = y;
+        self
+    }
+
+    pub fn rotation(mut self, rotation: f64) -> Self {
+        self.player.rotation = rotation;
+        self
+    }
+
+    pub fn health(mut self, health: i32) -> Self {
+        self.player.health = health;
+        self
+    }
+
+    pub fn shield(mut self, shield: i32) -> Self {
+        self.player.shield = shield;
+        self
+    }
+
+    pub fn team(mut self, team: Team) -> Self {
+        self.player.team = team;
+        self
+    }
+
+    pub fn dormant(mut self, dormant: bool) -> Self {
+        self.player.dormant = dormant;
+        self
+    }
+
+    pub fn scoped(mut self, scoped: bool) -> Self {
+        self.player.scoped = scoped;
+        self
+    }
+
+    pub fn weapon(mut self, weapon: i32) -> Self {
+        self.player.weapon = weapon;
+        self
+    }
+
+    pub fn credits(mut self, credits: i32) -> Self {
+        self.player.credits = credits;
+        self
+    }
+
+    /// Set the scoreboard of the player
+    /// # Arguments
+    /// * `kill` - Kills this match
+    /// * `death` - Deaths this match
+    /// * `assist` - Assists this match
+    /// * `acs` - Average combat score
+    pub fn score(mut self, kill: i32, death: i32, assist: i32, acs: i32) -> Self {
+        self.player.kill = kill;
+        self.player.death = death;
+        self.player.assist = assist;
+        self.player.acs = acs;
+        self
+    }
+
+    /// Mark the player as defusing the spike
+    /// # Arguments
+    /// * `time` - The time left on the defuse
+    pub fn defusing(mut self, time: f64) -> Self {
+        self.player.defusing = true;
+        self.player.defuse_time = time;
+        self
+    }
+
+    pub fn build(self) -> Player {
+        self.player
+    }
+}
+
+/// Data container for all players, sent as one array per field
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Players {
+    pub id: Vec<String>,
+    pub name: Vec<String>,
+    pub agent: Vec<i32>,
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub health: Vec<i32>,
+    pub team: Vec<Team>,
+    #[serde(with = "flag")]
+    pub dormant: Vec<bool>,
+    pub rotation: Vec<f64>,
+    #[serde(with = "flag")]
+    pub scoped: Vec<bool>,
+    pub weapon: Vec<i32>,
+    pub kill: Vec<i32>,
+    pub death: Vec<i32>,
+    pub assist: Vec<i32>,
+    pub acs: Vec<i32>,
+    pub shield: Vec<i32>,
+    pub credits: Vec<i32>,
+    #[serde(with = "flag")]
+    pub defusing: Vec<bool>,
+    pub defuse_time: Vec<f64>,
+}
+
+impl Players {
+    /// Number of players, taken from the `x` column
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    /// Append a player to every column
+    /// # Arguments
+    /// * `player` - The player to append
+    /// # Example
+    /// ```
+    /// use protocol::{Player, Players, Team};
+    /// let mut players = Players::default();
+    /// players.push(Player {
+    ///     id: "p1".to_string(), name: "Player 1".to_string(), agent: 0, x: 0.0, y: 0.0,
+    ///     health: 100, team: Team::Attackers, dormant: false, rotation: 0.0, scoped: false,
+    ///     weapon: 0, kill: 0, death: 0, assist: 0, acs: 0, shield: 0, credits: 800,
+    ///     defusing: false, defuse_time: -1.0,
+    /// });
+    /// assert_eq!(players.len(), 1);
+    /// ```
+    pub fn push(&mut self, player: Player) {
+        self.id.push(player.id);
+        self.name.push(player.name);
+        self.agent.push(player.agent);
+        self.x.push(player.x);
+        self.y.push(player.y);
+        self.health.push(player.health);
+        self.team.push(player.team);
+        self.dormant.push(player.dormant);
+        self.rotation.push(player.rotation);
+        self.scoped.push(player.scoped);
+        self.weapon.push(player.weapon);
+        self.kill.push(player.kill);
+        self.death.push(player.death);
+        self.assist.push(player.assist);
+        self.acs.push(player.acs);
+        self.shield.push(player.shield);
+        self.credits.push(player.credits);
+        self.defusing.push(player.defusing);
+        self.defuse_time.push(player.defuse_time);
+    }
+
+    /// Get the player at `index` as a row
+    /// # Arguments
+    /// * `index` - The index of the player in every column
+    /// # Returns
+    /// * `None` - If any column is shorter than `index`
+    pub fn get(&self, index: usize) -> Option<Player> {
+        Some(Player {
+            id: self.id.get(index)?.clone(),
+            name: self.name.get(index)?.clone(),
+            agent: *self.agent.get(index)?,
+            x: *self.x.get(index)?,
+            y: *self.y.get(index)?,
+            health: *self.health.get(index)?,
+            team: *self.team.get(index)?,
+            dormant: *self.dormant.get(index)?,
+            rotation: *self.rotation.get(index)?,
+            scoped: *self.scoped.get(index)?,
+            weapon: *self.weapon.get(index)?,
+            kill: *self.kill.get(index)?,
+            death: *self.death.get(index)?,
+            assist: *self.assist.get(index)?,
+            acs: *self.acs.get(index)?,
+            shield: *self.shield.get(index)?,
+            credits: *self.credits.get(index)?,
+            defusing: *self.defusing.get(index)?,
+            defuse_time: *self.defuse_time.get(index)?,
+        })
+    }
+
+    /// Find a player by its stable identifier
+    /// # Returns
+    /// * `None` - If no player has the identifier
+    pub fn find(&self, id: &str) -> Option<Player> {
+        self.get(self.id.iter().position(|player_id| player_id == id)?)
+    }
+
+    /// Convert the columns into a vector of players
+    /// # Example
+    /// ```
+    /// use protocol::Players;
+    /// let players = Players::default();
+    /// assert!(players.to_players().is_empty());
+    /// ```
+    pub fn to_players(&self) -> Vec<Player> {
+        (0..self.len()).filter_map(|i| self.get(i)).collect()
+    }
+}
+
+impl FromIterator<Player> for Players {
+    fn from_iter<I: IntoIterator<Item = Player>>(iter: I) -> Self {
+        let mut players = Players::default();
+        for player in iter {
+            players.push(player);
+        }
+        players
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{Event, Frame};
+use serde::{Deserialize, Serialize};
+
+/// A frame saved by the server, one JSON object per line of a recording
+/// # Example
+/// ```
+/// use protocol::{Data, Frame, Record, WireMessage};
+/// let frame = Frame::Keyframe { seq: 0, data: Data::builder().build() };
+/// let record = Record { seq: 0, received_at: 1_700_000_000_000, frame, events: Vec::new() };
+/// let line = record.to_json().unwrap();
+/// assert_eq!(Record::from_json(&line).unwrap(), record);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Position of the record in its recording, starting at 0
+    pub seq: u64,
+    /// When the server received the frame, in milliseconds since the Unix epoch
+    pub received_at: u64,
+    pub frame: Frame,
+    /// What the server found happened in the frame, see [`Event::between`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<Event>,
+}
@@ -0,0 +1,42 @@
+/// Room joined by peers that connect to `/`
+pub const DEFAULT_ROOM: &str = "default";
+
+/// Longest room id accepted in a request path
+pub const MAX_ROOM_LEN: usize = 64;
+
+/// The WebSocket request path for a room
+/// # Example
+/// ```
+/// use protocol::room_path;
+/// assert_eq!(room_path("scrim-1"), "/match/scrim-1");
+/// ```
+pub fn room_path(room: &str) -> String {
+    format!("/match/{}", room)
+}
+
+/// Read the room from a WebSocket request path of the form `/match/{id}`
+/// # Returns
+/// * `None` - If the path names no room or the id is not valid
+/// # Example
+/// ```
+/// use protocol::{room_from_path, DEFAULT_ROOM};
+/// assert_eq!(room_from_path("/match/scrim-1"), Some("scrim-1"));
+/// assert_eq!(room_from_path("/"), Some(DEFAULT_ROOM));
+/// assert_eq!(room_from_path("/match/../admin"), None);
+/// ```
+pub fn room_from_path(path: &str) -> Option<&str> {
+    if path.is_empty() || path == "/" {
+        return Some(DEFAULT_ROOM);
+    }
+    let room = path.strip_prefix("/match/")?.trim_end_matches('/');
+    is_valid_room(room).then_some(room)
+}
+
+/// Room ids are short and made of ASCII letters, digits, `-` and `_`
+pub fn is_valid_room(room: &str) -> bool {
+    !room.is_empty()
+        && room.len() <= MAX_ROOM_LEN
+        && room
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
@@ -0,0 +1,286 @@
+use crate::{Data, DeltaDecoder, DeltaError, Frame, GameInfo, Players};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Number of agents, valid agent ids are `0..AGENT_COUNT`
+pub const AGENT_COUNT: i32 = 22;
+/// Number of weapons, valid weapon ids are `0..WEAPON_COUNT`
+pub const WEAPON_COUNT: i32 = 20;
+pub const MAX_HEALTH: i32 = 100;
+pub const MAX_SHIELD: i32 = 50;
+
+/// Reason a frame was rejected, naming the offending field
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// A player column does not have one entry per player
+    LengthMismatch {
+        field: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// A value is outside of its allowed range
+    OutOfRange {
+        field: &'static str,
+        index: usize,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    /// A field that must hold at least one value is empty
+    Missing { field: &'static str },
+    /// A value that must be unique appears more than once
+    Duplicate { field: &'static str, index: usize },
+}
+
+impl ValidationError {
+    /// The name of the field that failed validation, e.g. `players.health`
+    pub fn field(&self) -> &'static str {
+        match self {
+            ValidationError::LengthMismatch { field, .. } => field,
+            ValidationError::OutOfRange { field, .. } => field,
+            ValidationError::Missing { field } => field,
+            ValidationError::Duplicate { field, .. } => field,
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::LengthMismatch {
+                field,
+                expected,
+                found,
+            } => write!(f, "{} has {} entries, expected {}", field, found, expected),
+            ValidationError::OutOfRange {
+                field,
+                index,
+                value,
+                min,
+                max,
+            } => write!(
+                f,
+                "{}[{}] = {} is outside {}..={}",
+                field, index, value, min, max
+            ),
+            ValidationError::Missing { field } => write!(f, "{} is empty", field),
+            ValidationError::Duplicate { field, index } => {
+                write!(f, "{}[{}] is a duplicate", field, index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Reason the server refused a message, sent back to the peer in a
+/// [`Notice::Rejected`](crate::Notice::Rejected)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum FrameError {
+    /// The message could not be parsed as a frame or control message
+    Malformed { detail: String },
+    /// The message is larger than the server accepts, in bytes
+    TooLarge { size: usize, max: usize },
+    /// The frame parsed but its game state failed [`Data::validate`]
+    Invalid { field: String, detail: String },
+    /// A delta arrived after an invalid frame, the producer's deltas are
+    /// dropped until it sends a valid keyframe
+    Quarantined,
+    /// A delta does not follow the last frame the server applied, e.g. after
+    /// a gap or before the first keyframe. `expected` is `None` when no
+    /// keyframe was received
+    OutOfSequence { expected: Option<u64>, found: u64 },
+}
+
+impl From<ValidationError> for FrameError {
+    fn from(error: ValidationError) -> Self {
+        FrameError::Invalid {
+            field: error.field().to_string(),
+            detail: error.to_string(),
+        }
+    }
+}
+
+impl From<DeltaError> for FrameError {
+    fn from(error: DeltaError) -> Self {
+        match error {
+            DeltaError::SequenceGap { expected, found } => FrameError::OutOfSequence {
+                expected: Some(expected),
+                found,
+            },
+            DeltaError::MissingKeyframe { found } => FrameError::OutOfSequence {
+                expected: None,
+                found,
+            },
+        }
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Malformed { detail } => write!(f, "malformed message: {}", detail),
+            FrameError::TooLarge { size, max } => {
+                write!(f, "message of {} bytes exceeds the limit of {}", size, max)
+            }
+            FrameError::Invalid { detail, .. } => write!(f, "invalid frame: {}", detail),
+            FrameError::Quarantined => {
+                write!(f, "deltas are dropped until a valid keyframe is sent")
+            }
+            FrameError::OutOfSequence {
+                expected: Some(expected),
+                found,
+            } => write!(f, "expected frame {}, received {}", expected, found),
+            FrameError::OutOfSequence {
+                expected: None,
+                found,
+            } => write!(f, "received frame {} before a keyframe", found),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+fn check_len<T>(field: &'static str, column: &[T], expected: usize) -> Result<(), ValidationError> {
+    if column.len() != expected {
+        return Err(ValidationError::LengthMismatch {
+            field,
+            expected,
+            found: column.len(),
+        });
+    }
+    Ok(())
+}
+
+fn check_range(
+    field: &'static str,
+    column: &[i32],
+    min: i32,
+    max: i32,
+) -> Result<(), ValidationError> {
+    match column.iter().position(|value| *value < min || *value > max) {
+        Some(index) => Err(ValidationError::OutOfRange {
+            field,
+            index,
+            value: column[index] as f64,
+            min: min as f64,
+            max: max as f64,
+        }),
+        None => Ok(()),
+    }
+}
+
+fn check_unique<T: PartialEq>(field: &'static str, column: &[T]) -> Result<(), ValidationError> {
+    match (0..column.len()).find(|&index| column[..index].contains(&column[index])) {
+        Some(index) => Err(ValidationError::Duplicate { field, index }),
+        None => Ok(()),
+    }
+}
+
+fn check_not_empty<T>(field: &'static str, column: &[T]) -> Result<(), ValidationError> {
+    if column.is_empty() {
+        return Err(ValidationError::Missing { field });
+    }
+    Ok(())
+}
+
+impl Players {
+    /// Check that every column has one entry per player, that player ids are
+    /// unique and that values are in range
+    /// # Example
+    /// ```
+    /// use protocol::Players;
+    /// let mut players = Players::default();
+    /// players.x.push(0.0);
+    /// assert_eq!(players.validate().unwrap_err().field(), "players.id");
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let expected = self.len();
+        check_len("players.id", &self.id, expected)?;
+        check_len("players.name", &self.name, expected)?;
+        check_len("players.agent", &self.agent, expected)?;
+        check_len("players.y", &self.y, expected)?;
+        check_len("players.health", &self.health, expected)?;
+        check_len("players.team", &self.team, expected)?;
+        check_len("players.dormant", &self.dormant, expected)?;
+        check_len("players.rotation", &self.rotation, expected)?;
+        check_len("players.scoped", &self.scoped, expected)?;
+        check_len("players.weapon", &self.weapon, expected)?;
+        check_len("players.kill", &self.kill, expected)?;
+        check_len("players.death", &self.death, expected)?;
+        check_len("players.assist", &self.assist, expected)?;
+        check_len("players.acs", &self.acs, expected)?;
+        check_len("players.shield", &self.shield, expected)?;
+        check_len("players.credits", &self.credits, expected)?;
+        check_len("players.defusing", &self.defusing, expected)?;
+        check_len("players.defuse_time", &self.defuse_time, expected)?;
+
+        check_unique("players.id", &self.id)?;
+        check_range("players.agent", &self.agent, 0, AGENT_COUNT - 1)?;
+        check_range("players.weapon", &self.weapon, 0, WEAPON_COUNT - 1)?;
+        check_range("players.health", &self.health, 0, MAX_HEALTH)?;
+        check_range("players.shield", &self.shield, 0, MAX_SHIELD)?;
+        Ok(())
+    }
+}
+
+impl GameInfo {
+    /// Check that the single value arrays the canvas reads are present
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_not_empty("game_info.round_time", &self.round_time)?;
+        if self.spike_planted {
+            check_not_empty("game_info.spike_x", &self.spike_x)?;
+            check_not_empty("game_info.spike_y", &self.spike_y)?;
+            check_not_empty("game_info.spike_time", &self.spike_time)?;
+        }
+        Ok(())
+    }
+}
+
+impl Data {
+    /// Validate the whole frame before it is drawn
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.players.validate()?;
+        self.game_info.validate()
+    }
+}
+
+impl DeltaDecoder {
+    /// Validate the game state a frame would produce, without applying it
+    /// # Returns
+    /// * `Err(FrameError::Invalid)` - If the state would fail [`Data::validate`]
+    /// * `Err(FrameError::OutOfSequence)` - If a delta does not follow the
+    ///   current state, it cannot be applied
+    /// # Example
+    /// ```
+    /// use protocol::{Data, DeltaDecoder, Frame, FrameError};
+    /// let decoder = DeltaDecoder::new();
+    /// let mut data = Data::builder().build();
+    /// data.game_info.round_time.clear();
+    /// let frame = Frame::Keyframe { seq: 0, data };
+    /// assert!(matches!(
+    ///     decoder.validate(&frame),
+    ///     Err(FrameError::Invalid { field, .. }) if field == "game_info.round_time"
+    /// ));
+    /// ```
+    pub fn validate(&self, frame: &Frame) -> Result<(), FrameError> {
+        match frame {
+            Frame::Keyframe { data, .. } => Ok(data.validate()?),
+            Frame::Delta { seq, patch, .. } => match self.state() {
+                Some((last, data)) if frame.follows(last) => {
+                    let mut next = data.clone();
+                    patch.apply(&mut next);
+                    Ok(next.validate()?)
+                }
+                Some((last, _)) => Err(FrameError::from(DeltaError::SequenceGap {
+                    expected: last + 1,
+                    found: *seq,
+                })),
+                None => Err(FrameError::from(DeltaError::MissingKeyframe {
+                    found: *seq,
+                })),
+            },
+        }
+    }
+}
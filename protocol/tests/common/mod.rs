@@ -0,0 +1,46 @@
+//! Fixtures shared by the integration tests, each test file uses some of them
+#![allow(dead_code)]
+
+use protocol::{
+    Data, Frame, GameInfo, GameInfoBuilder, Map, Player, PlayerBuilder, RoundOutcome, Team,
+};
+
+/// A living player at `100, 200` with 800 credits
+/// # Arguments
+/// * `id` - The id of the player, its name is the id in upper case
+/// * `team` - The side the player is on
+pub fn player(id: &str, team: Team) -> PlayerBuilder {
+    Player::builder(id)
+        .name(&id.to_uppercase())
+        .team(team)
+        .position(100.0, 200.0)
+        .credits(800)
+}
+
+/// Ten players `player-0` to `player-9`, the first five attacking
+pub fn roster() -> impl Iterator<Item = PlayerBuilder> {
+    (0..10).map(|index| {
+        let team = if index < 5 {
+            Team::Attackers
+        } else {
+            Team::Defenders
+        };
+        player(&format!("player-{}", index), team)
+    })
+}
+
+/// The first round of a match on Bind, with 100 seconds left
+pub fn game_info() -> GameInfoBuilder {
+    GameInfo::builder()
+        .map(Map::Bind)
+        .round_win_status(vec![RoundOutcome::Unplayed; 24])
+        .round_time(100.0)
+}
+
+/// The state a keyframe carries
+pub fn keyframe_data(frame: Frame) -> Data {
+    match frame {
+        Frame::Keyframe { data, .. } => data,
+        frame => panic!("expected a keyframe, got {:?}", frame),
+    }
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use common::{game_info, player};
+use protocol::{Control, Data, DeltaDecoder, DeltaEncoder, DeltaError, Frame, Team, WireMessage};
+
+/// One player moving and scoring every few ticks, the other standing still
+fn state(tick: i32) -> Data {
+    Data::builder()
+        .player(
+            player("a", Team::Attackers)
+                .position(tick as f64, 0.0)
+                .score(tick / 3, 0, 0, 0)
+                .build(),
+        )
+        .player(player("b", Team::Defenders).position(500.0, 500.0).build())
+        .game_info(game_info().build())
+        .build()
+}
+
+#[test]
+fn deltas_only_carry_changed_fields() {
+    let mut encoder = DeltaEncoder::new(10);
+    encoder.encode(state(0));
+    match encoder.encode(state(1)) {
+        Frame::Delta { seq, patch, .. } => {
+            assert_eq!(seq, 2);
+            assert_eq!(patch.players.x, Some(vec![1.0, 500.0]));
+            assert_eq!(patch.players.y, None);
+            assert_eq!(patch.players.kill, None);
+            assert!(patch.game_info.is_empty());
+        }
+        frame => panic!("expected a delta, got {:?}", frame),
+    }
+}
+
+#[test]
+fn decoder_reconstructs_state() {
+    let mut encoder = DeltaEncoder::new(4);
+    let mut decoder = DeltaDecoder::new();
+    for tick in 0..10 {
+        let frame = encoder.encode(state(tick));
+        assert_eq!(frame.is_keyframe(), tick % 4 == 0);
+        // Send every frame through the wire format
+        let frame = Frame::from_msgpack(&frame.to_msgpack().unwrap()).unwrap();
+        assert_eq!(decoder.apply(frame).unwrap(), &state(tick));
+    }
+}
+
+#[test]
+fn gap_requests_one_keyframe() {
+    let mut encoder = DeltaEncoder::new(100);
+    let mut decoder = DeltaDecoder::new();
+    decoder.apply(encoder.encode(state(0))).unwrap();
+    encoder.encode(state(1));
+    assert_eq!(
+        decoder.apply(encoder.encode(state(2))),
+        Err(DeltaError::SequenceGap {
+            expected: 2,
+            found: 3
+        })
+    );
+    assert!(decoder.take_keyframe_request());
+    assert!(decoder.apply(encoder.encode(state(3))).is_err());
+    assert!(!decoder.take_keyframe_request());
+
+    encoder.request_keyframe();
+    let frame = encoder.encode(state(4));
+    assert!(frame.is_keyframe());
+    assert_eq!(decoder.apply(frame).unwrap(), &state(4));
+}
+
+#[test]
+fn delta_before_keyframe() {
+    let mut decoder = DeltaDecoder::new();
+    let frame = Frame::Delta {
+        seq: 7,
+        base: None,
+        patch: Default::default(),
+    };
+    assert_eq!(
+        decoder.apply(frame),
+        Err(DeltaError::MissingKeyframe { found: 7 })
+    );
+    assert!(decoder.take_keyframe_request());
+}
+
+#[test]
+fn patches_send_flags_as_integers() {
+    let mut encoder = DeltaEncoder::new(10);
+    encoder.encode(state(0));
+    let mut next = state(0);
+    next.players.dormant = vec![true, false];
+    next.game_info.spike_planted = true;
+    let text = encoder.encode(next).to_json().unwrap();
+    assert!(text.contains(r#""dormant":[1,0]"#), "{}", text);
+    assert!(text.contains(r#""spike_planted":1"#), "{}", text);
+}
+
+#[test]
+fn coalesced_frames_reach_the_same_state() {
+    let mut encoder = DeltaEncoder::new(100);
+    let mut every_frame = DeltaDecoder::new();
+    let mut coalesced = DeltaDecoder::new();
+    let first = encoder.encode(state(0));
+    every_frame.apply(first.clone()).unwrap();
+    coalesced.apply(first).unwrap();
+
+    let mut pending: Option<Frame> = None;
+    for tick in 1..8 {
+        let frame = encoder.encode(state(tick));
+        every_frame.apply(frame.clone()).unwrap();
+        pending = Some(match pending {
+            Some(pending) => pending.coalesce(frame),
+            None => frame,
+        });
+    }
+    let merged = pending.unwrap();
+    assert_eq!(merged.seq(), 8);
+    assert!(merged.follows(1));
+    let merged = Frame::from_msgpack(&merged.to_msgpack().unwrap()).unwrap();
+    assert_eq!(coalesced.apply(merged).unwrap(), &state(7));
+    assert_eq!(every_frame.state().unwrap().1, &state(7));
+
+    // Deltas merged into a keyframe give a keyframe of the newest state
+    let keyframe = Frame::Keyframe {
+        seq: 8,
+        data: state(7),
+    };
+    let merged = keyframe.coalesce(encoder.encode(state(8)));
+    assert!(merged.is_keyframe());
+    assert_eq!(merged.seq(), 9);
+    assert_eq!(coalesced.apply(merged).unwrap(), &state(8));
+
+    // A delta that does not follow replaces the pending frame
+    encoder.encode(state(9));
+    let gap = encoder.encode(state(10));
+    let merged = Frame::Keyframe {
+        seq: 9,
+        data: state(8),
+    }
+    .coalesce(gap.clone());
+    assert_eq!(merged, gap);
+}
+
+#[test]
+fn delta_base_wire_format() {
+    let delta = Frame::Delta {
+        seq: 9,
+        base: None,
+        patch: Default::default(),
+    };
+    assert_eq!(
+        delta.to_json().unwrap(),
+        r#"{"type":"delta","seq":9,"patch":{}}"#
+    );
+    let merged = Frame::Delta {
+        seq: 9,
+        base: Some(4),
+        patch: Default::default(),
+    };
+    let text = merged.to_json().unwrap();
+    assert_eq!(text, r#"{"type":"delta","seq":9,"base":4,"patch":{}}"#);
+    assert!(Frame::from_json(&text).unwrap().follows(4));
+    assert!(!Frame::from_json(&text).unwrap().follows(8));
+
+    assert_eq!(
+        Control::SetFrameRate { fps: 15 }.to_json().unwrap(),
+        r#"{"type":"set_frame_rate","fps":15}"#
+    );
+}
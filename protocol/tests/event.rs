@@ -0,0 +1,142 @@
+mod common;
+
+use common::{game_info, player};
+use protocol::{Data, Event, Events, Map, RoundOutcome, Team, WireMessage};
+
+fn state(round_time: f64) -> Data {
+    Data::builder()
+        .players([
+            player("a1", Team::Attackers).build(),
+            player("a2", Team::Attackers).build(),
+            player("d1", Team::Defenders).build(),
+        ])
+        .game_info(game_info().round_time(round_time).build())
+        .build()
+}
+
+#[test]
+fn kills_name_the_only_enemy_that_scored() {
+    let previous = state(50.0);
+    let mut next = state(49.0);
+    next.players.health[2] = 0;
+    next.players.x[2] = 300.0;
+    next.players.kill[0] = 1;
+    assert_eq!(
+        Event::between(&previous, &next),
+        vec![Event::Kill {
+            victim: "d1".to_string(),
+            killer: Some("a1".to_string()),
+            x: 300.0,
+            y: 200.0,
+        }]
+    );
+
+    // Two attackers scored, the killer is unknown
+    next.players.kill[1] = 1;
+    assert!(matches!(
+        Event::between(&previous, &next)[..],
+        [Event::Kill { killer: None, .. }]
+    ));
+
+    // Players already dead are not killed again
+    assert_eq!(Event::between(&next, &next), vec![]);
+}
+
+#[test]
+fn plants_and_defuses() {
+    let previous = state(50.0);
+    let mut planted = state(49.0);
+    planted.game_info.spike_planted = true;
+    planted.game_info.spike_x = vec![12.0];
+    planted.game_info.spike_y = vec![34.0];
+    assert_eq!(
+        Event::between(&previous, &planted),
+        vec![Event::Plant { x: 12.0, y: 34.0 }]
+    );
+
+    let mut defusing = planted.clone();
+    defusing.players.defusing[2] = true;
+    assert_eq!(
+        Event::between(&planted, &defusing),
+        vec![Event::DefuseStarted {
+            player: "d1".to_string()
+        }]
+    );
+    assert_eq!(
+        Event::between(&defusing, &planted),
+        vec![Event::DefuseStopped {
+            player: "d1".to_string()
+        }]
+    );
+}
+
+#[test]
+fn round_transitions() {
+    let previous = state(2.0);
+    let mut ended = state(1.0);
+    ended.game_info.round_win_status[0] = RoundOutcome::Defenders;
+    ended.game_info.played_rounds = 1;
+    assert_eq!(
+        Event::between(&previous, &ended),
+        vec![Event::RoundEnded {
+            round: 1,
+            winner: Team::Defenders,
+        }]
+    );
+
+    // The round timer is reset for the next buy phase
+    let mut started = ended.clone();
+    started.game_info.round_time = vec![100.0];
+    assert_eq!(
+        Event::between(&ended, &started),
+        vec![Event::RoundStarted { round: 2 }]
+    );
+
+    let mut swapped = started.clone();
+    for team in swapped.players.team.iter_mut() {
+        *team = match team {
+            Team::Attackers => Team::Defenders,
+            Team::Defenders => Team::Attackers,
+        };
+    }
+    assert_eq!(
+        Event::between(&started, &swapped),
+        vec![Event::SidesSwapped]
+    );
+}
+
+#[test]
+fn no_events_across_matches() {
+    let previous = state(50.0);
+    let mut next = state(100.0);
+    next.game_info.map = Map::Haven;
+    next.players.health[0] = 0;
+    assert_eq!(Event::between(&previous, &next), vec![]);
+}
+
+#[test]
+fn events_wire_format() {
+    let events = Events {
+        seq: 7,
+        events: vec![
+            Event::Kill {
+                victim: "d1".to_string(),
+                killer: None,
+                x: 1.0,
+                y: 2.0,
+            },
+            Event::RoundEnded {
+                round: 3,
+                winner: Team::Attackers,
+            },
+        ],
+    };
+    assert_eq!(
+        events.to_json().unwrap(),
+        r#"{"type":"events","seq":7,"events":[{"kind":"kill","victim":"d1","killer":null,"x":1.0,"y":2.0},{"kind":"round_ended","round":3,"winner":0}]}"#
+    );
+    assert_eq!(
+        Events::from_msgpack(&events.to_msgpack().unwrap()).unwrap(),
+        events
+    );
+}
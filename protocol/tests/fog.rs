@@ -0,0 +1,103 @@
+mod common;
+
+use common::{game_info, keyframe_data, player};
+use protocol::{Data, DeltaDecoder, Event, Team, TeamView};
+
+fn state() -> Data {
+    let armed = |id, team, at| {
+        let player = player(id, team).position(at, at).rotation(90.0);
+        player.weapon(12).credits(3900).build()
+    };
+    Data::builder()
+        .players([
+            armed("a1", Team::Attackers, 100.0),
+            armed("d1", Team::Defenders, 500.0),
+        ])
+        .game_info(game_info().build())
+        .build()
+}
+
+#[test]
+fn enemies_never_seen_are_left_out() {
+    let mut data = state();
+    data.players.dormant = vec![false, true];
+    let seen = data.seen_by(Team::Attackers, None);
+    assert_eq!(seen.players.id, vec!["a1"]);
+
+    // Once seen, the enemy stays where it was last seen
+    let mut view = TeamView::new(Team::Attackers);
+    view.update(1, &data, true);
+    data.players.dormant[1] = false;
+    let (_, events) = view.update(2, &data, false);
+    assert!(events.is_empty());
+    data.players.dormant[1] = true;
+    data.players.x[1] = 900.0;
+    view.update(3, &data, false);
+    let seen = keyframe_data(view.keyframe().unwrap());
+    assert_eq!(seen.players.x, vec![100.0, 500.0]);
+}
+
+#[test]
+fn view_frames_rebuild_what_the_team_sees() {
+    let mut view = TeamView::new(Team::Attackers);
+    let mut decoder = DeltaDecoder::new();
+    let mut data = state();
+    let (frame, _) = view.update(1, &data, true);
+    assert!(frame.is_keyframe());
+    decoder.apply(frame).unwrap();
+
+    for seq in 2..6 {
+        data.players.x[1] += 10.0;
+        data.players.dormant[1] = seq >= 4;
+        let (frame, _) = view.update(seq, &data, false);
+        assert!(!frame.is_keyframe());
+        let seen = decoder.apply(frame).unwrap();
+        // The enemy was last seen at frame 3
+        let last_seen = if seq >= 4 { 520.0 } else { data.players.x[1] };
+        assert_eq!(seen.players.x, vec![100.0, last_seen]);
+        assert_eq!(seen, &view.keyframe().map(keyframe_data).unwrap());
+    }
+
+    // Producer keyframes are passed on as keyframes, for viewers that asked
+    let (frame, _) = view.update(6, &data, true);
+    assert!(frame.is_keyframe());
+}
+
+#[test]
+fn view_follows_the_team_across_side_swaps() {
+    let mut view = TeamView::new(Team::Attackers);
+    let mut data = state();
+    view.update(1, &data, true);
+
+    data.players.team = vec![Team::Defenders, Team::Attackers];
+    data.players.dormant = vec![false, true];
+    data.players.x = vec![111.0, 999.0];
+    let (frame, events) = view.update(2, &data, false);
+    assert_eq!(events, vec![Event::SidesSwapped]);
+    assert!(!frame.is_keyframe());
+    // "a1" is now a defender and still sees itself, "d1" stays hidden
+    let seen = keyframe_data(view.keyframe().unwrap());
+    assert_eq!(seen.players.x, vec![111.0, 500.0]);
+}
+
+#[test]
+fn kills_of_hidden_enemies_are_placed_where_they_were_seen() {
+    let mut view = TeamView::new(Team::Attackers);
+    let mut data = state();
+    view.update(1, &data, true);
+
+    data.players.dormant[1] = true;
+    data.players.health[1] = 0;
+    data.players.kill[0] = 1;
+    data.players.x[1] = 800.0;
+    let (_, events) = view.update(2, &data, false);
+    assert_eq!(
+        events,
+        vec![Event::Kill {
+            victim: "d1".to_string(),
+            killer: Some("a1".to_string()),
+            x: 500.0,
+            y: 500.0,
+        }]
+    );
+}
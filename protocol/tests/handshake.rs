@@ -0,0 +1,153 @@
+use protocol::{
+    Encoding, HandshakeError, Hello, HelloReply, Role, Team, WireMessage, PROTOCOL_VERSION,
+};
+
+#[test]
+fn hello_wire_format() {
+    let hello = Hello::new(Role::Viewer);
+    assert_eq!(
+        hello.to_json().unwrap(),
+        format!(
+            r#"{{"type":"hello","role":"viewer","version":{},"encodings":["msgpack","json"]}}"#,
+            PROTOCOL_VERSION
+        )
+    );
+    assert_eq!(Hello::from_json(&hello.to_json().unwrap()).unwrap(), hello);
+}
+
+#[test]
+fn accept_prefers_negotiated_encoding() {
+    let hello = Hello::new(Role::Producer);
+    assert_eq!(
+        hello.accept(Encoding::MessagePack),
+        Ok(Encoding::MessagePack)
+    );
+
+    let json_only = Hello {
+        encodings: vec![Encoding::Json],
+        ..hello
+    };
+    assert_eq!(json_only.accept(Encoding::MessagePack), Ok(Encoding::Json));
+}
+
+#[test]
+fn reject_incompatible_hello() {
+    let hello = Hello {
+        role: Role::Viewer,
+        version: PROTOCOL_VERSION + 1,
+        encodings: vec![Encoding::Json],
+        token: None,
+        team: None,
+    };
+    assert_eq!(
+        hello.accept(Encoding::Json),
+        Err(HandshakeError::UnsupportedVersion {
+            requested: PROTOCOL_VERSION + 1,
+            supported: PROTOCOL_VERSION,
+        })
+    );
+
+    let hello = Hello {
+        role: Role::Viewer,
+        version: PROTOCOL_VERSION,
+        encodings: vec![],
+        token: None,
+        team: None,
+    };
+    assert_eq!(
+        hello.accept(Encoding::Json),
+        Err(HandshakeError::NoCommonEncoding)
+    );
+}
+
+#[test]
+fn rejected_reply_round_trip() {
+    let reply = HelloReply::Rejected {
+        error: HandshakeError::ExpectedHello,
+    };
+    let bytes = reply.to_msgpack().unwrap();
+    assert_eq!(HelloReply::from_msgpack(&bytes).unwrap(), reply);
+}
+
+#[test]
+fn token_is_only_sent_when_set() {
+    let hello = Hello::new(Role::Producer);
+    assert!(!hello.to_json().unwrap().contains("token"));
+
+    let hello = hello.with_token("secret");
+    assert!(hello.to_json().unwrap().contains(r#""token":"secret""#));
+    assert_eq!(
+        Hello::from_msgpack(&hello.to_msgpack().unwrap()).unwrap(),
+        hello
+    );
+
+    let reply = HelloReply::Rejected {
+        error: HandshakeError::Unauthorized {
+            role: Role::Producer,
+        },
+    };
+    assert_eq!(
+        reply.to_json().unwrap(),
+        r#"{"type":"rejected","error":{"reason":"unauthorized","role":"producer"}}"#
+    );
+    if let HelloReply::Rejected { error } = reply {
+        assert_eq!(error.close_code(), 4401);
+    }
+    assert_eq!(HandshakeError::ExpectedHello.close_code(), 1008);
+}
+
+#[test]
+fn room_full_wire_format() {
+    let reply = HelloReply::Rejected {
+        error: HandshakeError::RoomFull { role: Role::Viewer },
+    };
+    assert_eq!(
+        reply.to_json().unwrap(),
+        r#"{"type":"rejected","error":{"reason":"room_full","role":"viewer"}}"#
+    );
+    assert_eq!(
+        HelloReply::from_json(&reply.to_json().unwrap()).unwrap(),
+        reply
+    );
+    assert_eq!(
+        HandshakeError::RoomFull { role: Role::Viewer }.close_code(),
+        1013
+    );
+}
+
+#[test]
+fn replaying_wire_format() {
+    let reply = HelloReply::Rejected {
+        error: HandshakeError::Replaying,
+    };
+    assert_eq!(
+        reply.to_json().unwrap(),
+        r#"{"type":"rejected","error":{"reason":"replaying"}}"#
+    );
+    assert_eq!(HandshakeError::Replaying.close_code(), 1008);
+}
+
+#[test]
+fn team_is_only_sent_when_set() {
+    let hello = Hello::new(Role::Viewer);
+    assert!(!hello.to_json().unwrap().contains("team"));
+    let hello = hello.with_team(Team::Attackers);
+    assert_eq!(
+        Hello::from_json(&hello.to_json().unwrap()).unwrap().team,
+        Some(Team::Attackers)
+    );
+
+    let reply = HelloReply::Welcome {
+        version: PROTOCOL_VERSION,
+        role: Role::Viewer,
+        encoding: Encoding::Json,
+        team: Some(Team::Defenders),
+    };
+    assert_eq!(
+        reply.to_json().unwrap(),
+        format!(
+            r#"{{"type":"welcome","version":{},"role":"viewer","encoding":"json","team":1}}"#,
+            PROTOCOL_VERSION
+        )
+    );
+}
@@ -0,0 +1,44 @@
+use protocol::{Coordinates, GameInfo, Map, MINIMAP_SIZE};
+
+#[test]
+fn calibrated_maps_project_onto_the_minimap() {
+    for map in Map::ALL {
+        let Some(calibration) = map.calibration() else {
+            continue;
+        };
+        // The map origin lies somewhere on the minimap image
+        let (x, y) = calibration.to_minimap(0.0, 0.0);
+        assert!((0.0..=1.5 * MINIMAP_SIZE).contains(&x), "{:?}", map);
+        assert!((0.0..=1.5 * MINIMAP_SIZE).contains(&y), "{:?}", map);
+    }
+}
+
+#[test]
+fn world_axes_are_swapped() {
+    let info = GameInfo::builder()
+        .map(Map::Bind)
+        .coordinates(Coordinates::World)
+        .build();
+    let (origin_x, origin_y) = info.to_minimap(0.0, 0.0).unwrap();
+    // Moving along the world y axis moves right on the minimap
+    let (x, y) = info.to_minimap(0.0, 1000.0).unwrap();
+    assert!(x > origin_x);
+    assert_eq!(y, origin_y);
+    // Moving along the world x axis moves up on the minimap
+    let (x, y) = info.to_minimap(1000.0, 0.0).unwrap();
+    assert_eq!(x, origin_x);
+    assert!(y < origin_y);
+}
+
+#[test]
+fn uncalibrated_maps_only_accept_minimap_pixels() {
+    let info = GameInfo::builder().map(Map::Kasbah).build();
+    assert_eq!(info.coordinates, Coordinates::Minimap);
+    assert_eq!(info.to_minimap(10.0, 20.0), Some((10.0, 20.0)));
+
+    let info = GameInfo::builder()
+        .map(Map::Kasbah)
+        .coordinates(Coordinates::World)
+        .build();
+    assert_eq!(info.to_minimap(10.0, 20.0), None);
+}
@@ -0,0 +1,52 @@
+use protocol::{Control, Data, DeltaEncoder, GameInfo, Map, Record, WireMessage};
+
+fn state(map: Map, played_rounds: i32) -> Data {
+    Data::builder()
+        .game_info(
+            GameInfo::builder()
+                .map(map)
+                .played_rounds(played_rounds)
+                .build(),
+        )
+        .build()
+}
+
+#[test]
+fn records_are_single_json_lines() {
+    let mut encoder = DeltaEncoder::new(10);
+    encoder.encode(state(Map::Ascent, 0));
+    let record = Record {
+        seq: 1,
+        received_at: 1_700_000_000_000,
+        frame: encoder.encode(state(Map::Ascent, 1)),
+        events: Vec::new(),
+    };
+    let line = record.to_json().unwrap();
+    assert!(!line.contains('\n'));
+    assert!(line.starts_with(r#"{"seq":1,"received_at":1700000000000,"frame":{"type":"delta""#));
+    assert_eq!(Record::from_json(&line).unwrap(), record);
+}
+
+#[test]
+fn new_match_on_map_change_or_round_reset() {
+    let previous = state(Map::Haven, 5).game_info;
+    assert!(!state(Map::Haven, 5).game_info.starts_new_match(&previous));
+    assert!(!state(Map::Haven, 6).game_info.starts_new_match(&previous));
+    assert!(state(Map::Haven, 0).game_info.starts_new_match(&previous));
+    assert!(state(Map::Lotus, 6).game_info.starts_new_match(&previous));
+}
+
+#[test]
+fn replay_controls_wire_format() {
+    assert_eq!(Control::Pause.to_json().unwrap(), r#"{"type":"pause"}"#);
+    assert_eq!(Control::Resume.to_json().unwrap(), r#"{"type":"resume"}"#);
+    let seek = Control::Seek { position: 90_000 };
+    assert_eq!(
+        seek.to_json().unwrap(),
+        r#"{"type":"seek","position":90000}"#
+    );
+    assert_eq!(
+        Control::from_msgpack(&seek.to_msgpack().unwrap()).unwrap(),
+        seek
+    );
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use common::{game_info, roster};
+use protocol::{
+    Data, Frame, GameInfo, Map, Notice, Player, Players, RoundOutcome, Team, WireMessage,
+};
+
+/// The ten players of `frame`, with every field set
+fn players() -> Vec<Player> {
+    roster()
+        .enumerate()
+        .map(|(index, player)| {
+            player
+                .agent(index as i32)
+                .position(512.5, 128.25)
+                .rotation(270.0)
+                .health(74)
+                .shield(25)
+                .scoped(true)
+                .weapon(18)
+                .score(12, 7, 3, 245)
+                .credits(3900)
+                .build()
+        })
+        .collect()
+}
+
+fn frame() -> Data {
+    Data::builder()
+        .players(players())
+        .game_info(
+            game_info()
+                .map(Map::Kasbah)
+                .spike(300.0, 700.0, 32.5)
+                .round_win_status(vec![
+                    RoundOutcome::Attackers,
+                    RoundOutcome::Defenders,
+                    RoundOutcome::Defenders,
+                    RoundOutcome::Unplayed,
+                ])
+                .played_rounds(3)
+                .round_time(32.5)
+                .build(),
+        )
+        .build()
+}
+
+#[test]
+fn json_round_trip() {
+    let data = frame();
+    let text = data.to_json().unwrap();
+    assert_eq!(Data::from_json(&text).unwrap(), data);
+}
+
+#[test]
+fn players_rows_round_trip() {
+    let rows = players();
+    let players: Players = rows.iter().cloned().collect();
+    assert_eq!(players.len(), 10);
+    assert_eq!(players.to_players(), rows);
+}
+
+#[test]
+fn decodes_existing_wire_format() {
+    let text = r#"{
+        "players": {
+            "id": ["puuid-3"], "name": ["Player 3"], "agent": [3], "x": [10.0], "y": [20.0], "health": [100], "team": [1],
+            "dormant": [0], "rotation": [90.0], "scoped": [0], "weapon": [13],
+            "kill": [1], "death": [0], "assist": [2], "acs": [180], "shield": [50],
+            "credits": [4500], "defusing": [0], "defuse_time": [-1.0]
+        },
+        "game_info": {
+            "spike_planted": 0, "spike_x": [0], "spike_y": [0], "spike_time": [0],
+            "round_win_status": [0, 2], "played_rounds": 1, "max_rounds": 24,
+            "round_time": [95.0]
+        }
+    }"#;
+    let data = Data::from_json(text).unwrap();
+    let player = data.players.get(0).unwrap();
+    assert_eq!(data.game_info.map, Map::Ascent);
+    assert_eq!(player.id, "puuid-3");
+    assert_eq!(player.agent, 3);
+    assert_eq!(player.weapon, 13);
+    assert_eq!(player.team, Team::Defenders);
+    assert!(!player.dormant);
+    assert_eq!(
+        data.game_info.round_win_status,
+        vec![RoundOutcome::Attackers, RoundOutcome::Unplayed]
+    );
+    // Flags are written back as the integers they were read from
+    let json = data.to_json().unwrap();
+    assert!(json.contains(r#""team":[1]"#));
+    assert!(json.contains(r#""spike_planted":0"#));
+    assert!(json.contains(r#""round_win_status":[0,2]"#));
+}
+
+#[test]
+fn invalid_flags_are_parse_errors() {
+    let json = frame().to_json().unwrap();
+    for (valid, invalid) in [
+        (r#""team":[0,"#, r#""team":[2,"#),
+        (r#""dormant":[0,"#, r#""dormant":[3,"#),
+        (r#""round_win_status":[0,"#, r#""round_win_status":[-1,"#),
+        (r#""spike_planted":1"#, r#""spike_planted":2"#),
+    ] {
+        assert!(json.contains(valid), "{} not found", valid);
+        let err = Data::from_json(&json.replacen(valid, invalid, 1)).unwrap_err();
+        assert!(err.to_string().starts_with("expected"), "{}", err);
+    }
+}
+
+#[test]
+fn builder_without_spike() {
+    let info = GameInfo::builder().round_time(100.0).build();
+    assert!(!info.spike_planted);
+    assert_eq!(info.spike_x, vec![0.0]);
+    assert_eq!(info.max_rounds, 24);
+}
+
+#[test]
+fn msgpack_round_trip() {
+    let data = frame();
+    let bytes = data.to_msgpack().unwrap();
+    assert!(bytes.len() < data.to_json().unwrap().len());
+    assert_eq!(Data::from_msgpack(&bytes).unwrap(), data);
+}
+
+#[test]
+fn unknown_map_is_a_parse_error() {
+    let json = frame().to_json().unwrap();
+    assert!(json.contains(r#""map":"Kasbah""#));
+    assert!(Data::from_json(&json.replace("Kasbah", "Atlantis")).is_err());
+}
+
+#[test]
+fn notices_are_not_frames() {
+    let stalled = Notice::Stalled { silent_for: 5000 };
+    let text = stalled.to_json().unwrap();
+    assert_eq!(text, r#"{"type":"stalled","silent_for":5000}"#);
+    assert!(Frame::from_json(&text).is_err());
+    assert_eq!(
+        Notice::from_msgpack(&stalled.to_msgpack().unwrap()).unwrap(),
+        stalled
+    );
+    assert_eq!(Notice::Resumed.to_json().unwrap(), r#"{"type":"resumed"}"#);
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use common::{game_info, roster};
+use protocol::{
+    Data, DataPatch, DeltaDecoder, Frame, FrameError, Notice, ValidationError, WireMessage,
+};
+
+fn frame() -> Data {
+    // Every player on the same agent, as in a mirror match
+    let players = roster().map(|player| player.agent(21).weapon(19).shield(50).build());
+    Data::builder()
+        .players(players)
+        .game_info(game_info().build())
+        .build()
+}
+
+#[test]
+fn valid_frame() {
+    assert_eq!(frame().validate(), Ok(()));
+}
+
+#[test]
+fn mismatched_column_length() {
+    let mut data = frame();
+    data.players.health.pop();
+    assert_eq!(
+        data.validate(),
+        Err(ValidationError::LengthMismatch {
+            field: "players.health",
+            expected: 10,
+            found: 9,
+        })
+    );
+}
+
+#[test]
+fn agent_id_out_of_range() {
+    let mut data = frame();
+    data.players.agent[3] = 22;
+    let err = data.validate().unwrap_err();
+    assert_eq!(err.field(), "players.agent");
+    assert_eq!(err.to_string(), "players.agent[3] = 22 is outside 0..=21");
+}
+
+#[test]
+fn duplicate_player_id() {
+    let mut data = frame();
+    data.players.id[7] = "player-2".to_string();
+    assert_eq!(
+        data.validate(),
+        Err(ValidationError::Duplicate {
+            field: "players.id",
+            index: 7
+        })
+    );
+}
+
+#[test]
+fn health_and_shield_out_of_range() {
+    let mut data = frame();
+    data.players.health[0] = 101;
+    assert_eq!(data.validate().unwrap_err().field(), "players.health");
+
+    let mut data = frame();
+    data.players.shield[9] = -1;
+    assert_eq!(data.validate().unwrap_err().field(), "players.shield");
+}
+
+#[test]
+fn planted_spike_without_position() {
+    let mut data = frame();
+    data.game_info.spike_planted = true;
+    data.game_info.spike_x.clear();
+    assert_eq!(
+        data.validate(),
+        Err(ValidationError::Missing {
+            field: "game_info.spike_x"
+        })
+    );
+}
+
+#[test]
+fn deltas_are_validated_against_the_state() {
+    let mut decoder = DeltaDecoder::new();
+    decoder
+        .apply(Frame::Keyframe {
+            seq: 0,
+            data: frame(),
+        })
+        .unwrap();
+
+    let mut broken = frame();
+    broken.players.health[3] = 250;
+    let delta = Frame::Delta {
+        seq: 1,
+        base: None,
+        patch: DataPatch::diff(&frame(), &broken),
+    };
+    assert!(matches!(
+        decoder.validate(&delta),
+        Err(FrameError::Invalid { field, .. }) if field == "players.health"
+    ));
+}
+
+#[test]
+fn deltas_that_do_not_follow_the_state_are_refused() {
+    let valid = Frame::Delta {
+        seq: 1,
+        base: None,
+        patch: DataPatch::diff(&frame(), &frame()),
+    };
+    // No keyframe yet, the delta cannot be applied
+    let mut decoder = DeltaDecoder::new();
+    assert_eq!(
+        decoder.validate(&valid),
+        Err(FrameError::OutOfSequence {
+            expected: None,
+            found: 1,
+        })
+    );
+
+    decoder
+        .apply(Frame::Keyframe {
+            seq: 0,
+            data: frame(),
+        })
+        .unwrap();
+    assert_eq!(decoder.validate(&valid), Ok(()));
+    let gap = Frame::Delta {
+        seq: 5,
+        base: None,
+        patch: DataPatch::diff(&frame(), &frame()),
+    };
+    let error = decoder.validate(&gap).unwrap_err();
+    assert_eq!(
+        error,
+        FrameError::OutOfSequence {
+            expected: Some(1),
+            found: 5,
+        }
+    );
+    assert_eq!(
+        Notice::Rejected {
+            seq: Some(5),
+            error
+        }
+        .to_json()
+        .unwrap(),
+        r#"{"type":"rejected","seq":5,"error":{"reason":"out_of_sequence","expected":1,"found":5}}"#
+    );
+}
+
+#[test]
+fn rejection_wire_format() {
+    let mut data = frame();
+    data.players.agent[0] = 99;
+    let error = FrameError::from(data.validate().unwrap_err());
+    let notice = Notice::Rejected {
+        seq: Some(7),
+        error,
+    };
+    assert_eq!(
+        notice.to_json().unwrap(),
+        r#"{"type":"rejected","seq":7,"error":{"reason":"invalid","field":"players.agent","detail":"players.agent[0] = 99 is outside 0..=21"}}"#
+    );
+    assert_eq!(
+        Notice::from_json(&notice.to_json().unwrap()).unwrap(),
+        notice
+    );
+
+    let quarantined = Notice::Rejected {
+        seq: None,
+        error: FrameError::Quarantined,
+    };
+    assert_eq!(
+        quarantined.to_json().unwrap(),
+        r#"{"type":"rejected","error":{"reason":"quarantined"}}"#
+    );
+    assert_eq!(
+        Notice::from_msgpack(&quarantined.to_msgpack().unwrap()).unwrap(),
+        quarantined
+    );
+}
@@ -0,0 +1,211 @@
+use protocol::{HandshakeError, Role, Team};
+use std::net::IpAddr;
+
+/// Tokens accepted for each role. A role without tokens is open to anyone.
+/// Viewer tokens give the full game state, team tokens only admit viewers to
+/// the view of their team. Admin tokens give access to the status pages
+#[derive(Debug, Clone, Default)]
+pub struct Auth {
+    producer_tokens: Vec<String>,
+    viewer_tokens: Vec<String>,
+    /// Indexed by [`Team::index`]
+    team_tokens: [Vec<String>; 2],
+    admin_tokens: Vec<String>,
+}
+
+impl Auth {
+    pub fn new(producer_tokens: Vec<String>, viewer_tokens: Vec<String>) -> Self {
+        Auth {
+            producer_tokens,
+            viewer_tokens,
+            team_tokens: Default::default(),
+            admin_tokens: Vec::new(),
+        }
+    }
+
+    /// Admit viewers presenting one of `tokens` as `team` only, whatever
+    /// team they ask for
+    pub fn with_team_tokens(mut self, team: Team, tokens: Vec<String>) -> Self {
+        self.team_tokens[team.index()] = tokens;
+        self
+    }
+
+    /// Give clients presenting one of `tokens` access to the status pages
+    pub fn with_admin_tokens(mut self, tokens: Vec<String>) -> Self {
+        self.admin_tokens = tokens;
+        self
+    }
+
+    /// Whether a client may read `/status` and `/metrics`. Without admin
+    /// tokens only clients on this machine may
+    /// # Arguments
+    /// * `ip` - The address the client connected from
+    /// * `token` - The bearer token of the request, if any
+    pub fn allows_admin(&self, ip: IpAddr, token: Option<&str>) -> bool {
+        if self.admin_tokens.is_empty() {
+            return ip.is_loopback();
+        }
+        let token = token.unwrap_or_default();
+        self.admin_tokens.iter().fold(false, |valid, expected| {
+            valid | constant_time_eq(expected, token)
+        })
+    }
+
+    fn tokens(&self, role: Role) -> &[String] {
+        match role {
+            Role::Producer => &self.producer_tokens,
+            Role::Viewer => &self.viewer_tokens,
+        }
+    }
+
+    /// Whether some viewers are limited to the view of their team. Without
+    /// team tokens any viewer that can connect may ask for the full state
+    pub fn enforces_teams(&self) -> bool {
+        self.team_tokens.iter().any(|tokens| !tokens.is_empty())
+    }
+
+    /// Whether peers joining in `role` must present a token
+    pub fn is_required(&self, role: Role) -> bool {
+        let team_tokens = match role {
+            Role::Producer => false,
+            Role::Viewer => self.enforces_teams(),
+        };
+        !self.tokens(role).is_empty() || team_tokens
+    }
+
+    /// Check the token a peer sent in its hello
+    /// # Arguments
+    /// * `role` - The role the peer asked for
+    /// * `token` - The token from the hello, if any
+    /// * `team` - The team whose view a viewer asked for
+    /// # Returns
+    /// * `Ok(Option<Team>)` - The team whose view the peer is sent, `None`
+    ///   for the full game state
+    pub fn check(
+        &self,
+        role: Role,
+        token: Option<&str>,
+        team: Option<Team>,
+    ) -> Result<Option<Team>, HandshakeError> {
+        let team = match role {
+            Role::Producer => None,
+            Role::Viewer => team,
+        };
+        if !self.is_required(role) {
+            return Ok(team);
+        }
+        let token = token.unwrap_or_default();
+        // Compare against every token so the time taken does not reveal which matched
+        let matches = |tokens: &[String]| {
+            tokens.iter().fold(false, |valid, expected| {
+                valid | constant_time_eq(expected, token)
+            })
+        };
+        let full = matches(self.tokens(role));
+        let teams = Team::ALL.map(|team| matches(&self.team_tokens[team.index()]));
+        // A team token limits the viewer to its team, whatever it asked for
+        match Team::ALL.into_iter().find(|team| teams[team.index()]) {
+            _ if full => Ok(team),
+            Some(team) if role == Role::Viewer => Ok(Some(team)),
+            _ => Err(HandshakeError::Unauthorized { role }),
+        }
+    }
+}
+
+/// Compare two strings in time that only depends on their lengths
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth() -> Auth {
+        Auth::new(vec!["producer".to_string()], vec!["observer".to_string()])
+            .with_team_tokens(Team::Attackers, vec!["coach-a".to_string()])
+            .with_team_tokens(Team::Defenders, vec!["coach-d".to_string()])
+    }
+
+    #[test]
+    fn team_tokens_pin_viewers_to_their_team() {
+        let auth = auth();
+        let check = |token, team| auth.check(Role::Viewer, Some(token), team);
+        assert_eq!(check("coach-a", None), Ok(Some(Team::Attackers)));
+        assert_eq!(
+            check("coach-a", Some(Team::Defenders)),
+            Ok(Some(Team::Attackers))
+        );
+        assert_eq!(check("coach-d", None), Ok(Some(Team::Defenders)));
+        // Observers may pick a team or see everything
+        assert_eq!(check("observer", None), Ok(None));
+        assert_eq!(
+            check("observer", Some(Team::Defenders)),
+            Ok(Some(Team::Defenders))
+        );
+    }
+
+    #[test]
+    fn team_tokens_admit_nothing_else() {
+        let auth = auth();
+        let unauthorized = |role| Err(HandshakeError::Unauthorized { role });
+        assert_eq!(
+            auth.check(Role::Producer, Some("coach-a"), None),
+            unauthorized(Role::Producer)
+        );
+        assert_eq!(
+            auth.check(Role::Viewer, Some("wrong"), Some(Team::Attackers)),
+            unauthorized(Role::Viewer)
+        );
+        assert_eq!(
+            auth.check(Role::Viewer, None, None),
+            unauthorized(Role::Viewer)
+        );
+        // Producers are never limited to a team
+        assert_eq!(
+            auth.check(Role::Producer, Some("producer"), Some(Team::Attackers)),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn status_pages_are_local_without_admin_tokens() {
+        let local = IpAddr::from([127, 0, 0, 1]);
+        let remote = IpAddr::from([10, 0, 0, 7]);
+        let auth = Auth::default();
+        assert!(auth.allows_admin(local, None));
+        assert!(!auth.allows_admin(remote, None));
+
+        let auth = auth.with_admin_tokens(vec!["ops".to_string()]);
+        assert!(auth.allows_admin(remote, Some("ops")));
+        assert!(!auth.allows_admin(remote, Some("wrong")));
+        assert!(!auth.allows_admin(local, None));
+    }
+
+    #[test]
+    fn without_team_tokens_viewers_choose_their_view() {
+        let auth = Auth::new(Vec::new(), vec!["observer".to_string()]);
+        assert!(!auth.enforces_teams());
+        assert_eq!(
+            auth.check(Role::Viewer, Some("observer"), Some(Team::Attackers)),
+            Ok(Some(Team::Attackers))
+        );
+        assert_eq!(auth.check(Role::Viewer, Some("observer"), None), Ok(None));
+    }
+
+    #[test]
+    fn team_tokens_require_every_viewer_to_authenticate() {
+        let auth = Auth::default().with_team_tokens(Team::Attackers, vec!["coach".to_string()]);
+        assert!(auth.is_required(Role::Viewer));
+        assert!(!auth.is_required(Role::Producer));
+        assert!(auth.enforces_teams());
+        assert_eq!(
+            auth.check(Role::Viewer, None, None),
+            Err(HandshakeError::Unauthorized { role: Role::Viewer })
+        );
+    }
+}
@@ -0,0 +1,456 @@
+use crate::queue::QUEUE_CAPACITY;
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io, path::PathBuf, time::Duration};
+
+/// Relay between Valorant radar producers and the canvas viewers
+///
+/// Settings are read from the config file, then from the environment and the
+/// command line, the last one given wins
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Address to listen on [default: localhost:27017]
+    pub bind: Option<String>,
+    /// TOML file to read the settings from
+    #[arg(short, long, env = "RADAR_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Origins browsers may connect from, e.g. `https://radar.example.com`.
+    /// Any origin is allowed when none is given
+    #[arg(
+        long = "allowed-origin",
+        env = "RADAR_ALLOWED_ORIGINS",
+        value_delimiter = ','
+    )]
+    pub allowed_origins: Vec<String>,
+    /// Largest WebSocket message accepted, in bytes
+    #[arg(long, env = "RADAR_MAX_MESSAGE_SIZE")]
+    pub max_message_size: Option<usize>,
+    /// Tokens producers may authenticate with
+    #[arg(
+        long = "producer-token",
+        env = "RADAR_PRODUCER_TOKENS",
+        value_delimiter = ','
+    )]
+    pub producer_tokens: Vec<String>,
+    /// Tokens viewers may authenticate with
+    #[arg(
+        long = "viewer-token",
+        env = "RADAR_VIEWER_TOKENS",
+        value_delimiter = ','
+    )]
+    pub viewer_tokens: Vec<String>,
+    /// Tokens admitting viewers to the view of team 0 only, e.g. its coach
+    #[arg(
+        long = "team0-token",
+        env = "RADAR_TEAM0_TOKENS",
+        value_delimiter = ','
+    )]
+    pub team0_tokens: Vec<String>,
+    /// Tokens admitting viewers to the view of team 1 only
+    #[arg(
+        long = "team1-token",
+        env = "RADAR_TEAM1_TOKENS",
+        value_delimiter = ','
+    )]
+    pub team1_tokens: Vec<String>,
+    /// Tokens that give access to `/status` and `/metrics`, which are only
+    /// served to local clients when none is given
+    #[arg(
+        long = "admin-token",
+        env = "RADAR_ADMIN_TOKENS",
+        value_delimiter = ','
+    )]
+    pub admin_tokens: Vec<String>,
+    /// Directory match recordings are written to, recording is off when unset
+    #[arg(long, env = "RADAR_RECORD_DIR")]
+    pub record_dir: Option<PathBuf>,
+    /// Directory holding the built canvas
+    #[arg(long, env = "RADAR_WEB_DIR")]
+    pub web_dir: Option<PathBuf>,
+    /// Directory holding the canvas images, used when they are missing from the build
+    #[arg(long, env = "RADAR_ASSETS_DIR")]
+    pub assets_dir: Option<PathBuf>,
+    /// Most rooms open at once
+    #[arg(long, env = "RADAR_MAX_ROOMS")]
+    pub max_rooms: Option<usize>,
+    /// Most producers in one room
+    #[arg(long, env = "RADAR_MAX_PRODUCERS")]
+    pub max_producers: Option<usize>,
+    /// Most viewers in one room
+    #[arg(long, env = "RADAR_MAX_VIEWERS")]
+    pub max_viewers: Option<usize>,
+    /// Game-state frames a peer may have waiting before it is considered behind
+    #[arg(long, env = "RADAR_QUEUE_CAPACITY")]
+    pub queue_capacity: Option<usize>,
+    /// Seconds without a frame before viewers are told the feed stalled
+    #[arg(long, env = "RADAR_STALL_TIMEOUT")]
+    pub stall_timeout: Option<u64>,
+    /// PEM certificate chain, serves `wss://` and `https://` instead of plain
+    /// connections
+    #[arg(long, env = "RADAR_TLS_CERT", requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key of the certificate
+    #[arg(long, env = "RADAR_TLS_KEY", requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+    /// Least severe messages logged
+    #[arg(long, env = "RADAR_LOG_LEVEL")]
+    pub log_level: Option<LogLevel>,
+    /// How log lines are written
+    #[arg(long, env = "RADAR_LOG_FORMAT")]
+    pub log_format: Option<LogFormat>,
+    /// Serve a recorded match in the default room instead of relaying producers
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+    /// Replay speed multiplier
+    #[arg(long, default_value_t = 1.0, requires = "replay", value_parser = positive)]
+    pub speed: f64,
+    /// Start the replay over once it ends
+    #[arg(long = "loop", requires = "replay")]
+    pub looping: bool,
+}
+
+fn positive(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(number) if number > 0.0 => Ok(number),
+        _ => Err("expected a positive number".to_string()),
+    }
+}
+
+/// How much the server logs
+#[derive(Serialize, Deserialize, ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for tracing::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => tracing::Level::ERROR,
+            LogLevel::Warn => tracing::Level::WARN,
+            LogLevel::Info => tracing::Level::INFO,
+            LogLevel::Debug => tracing::Level::DEBUG,
+            LogLevel::Trace => tracing::Level::TRACE,
+        }
+    }
+}
+
+/// How log lines are written
+#[derive(Serialize, Deserialize, ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Readable lines with `key=value` fields
+    #[default]
+    Text,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+/// Limits on the rooms and their peers
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoomLimits {
+    pub max_rooms: usize,
+    pub max_producers: usize,
+    pub max_viewers: usize,
+    pub queue_capacity: usize,
+    /// Seconds
+    pub stall_timeout: u64,
+}
+
+impl Default for RoomLimits {
+    fn default() -> Self {
+        RoomLimits {
+            max_rooms: 64,
+            max_producers: 4,
+            max_viewers: 256,
+            queue_capacity: QUEUE_CAPACITY,
+            stall_timeout: 5,
+        }
+    }
+}
+
+impl RoomLimits {
+    pub fn stall_timeout(&self) -> Duration {
+        Duration::from_secs(self.stall_timeout)
+    }
+}
+
+/// Tokens accepted for each role
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub producer_tokens: Vec<String>,
+    /// Viewers with these tokens are sent the full game state
+    pub viewer_tokens: Vec<String>,
+    /// Viewers with these tokens are only sent what team 0 sees
+    pub team0_tokens: Vec<String>,
+    /// Viewers with these tokens are only sent what team 1 sees
+    pub team1_tokens: Vec<String>,
+    /// Bearer tokens for `/status` and `/metrics`
+    pub admin_tokens: Vec<String>,
+}
+
+/// Certificate and key to accept `wss://` and `https://` connections with
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM certificate chain, leaf first
+    pub cert: PathBuf,
+    /// PEM private key
+    pub key: PathBuf,
+}
+
+/// Settings of the server, as read from the config file. Every field is
+/// optional in the file
+/// # Example
+/// ```toml
+/// bind = "0.0.0.0:27017"
+/// allowed_origins = ["https://radar.example.com"]
+/// log_level = "debug"
+///
+/// [auth]
+/// producer_tokens = ["secret"]
+/// team0_tokens = ["coach-a"]
+///
+/// [rooms]
+/// max_viewers = 32
+///
+/// [tls]
+/// cert = "/etc/letsencrypt/live/radar.example.com/fullchain.pem"
+/// key = "/etc/letsencrypt/live/radar.example.com/privkey.pem"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bind: String,
+    /// Origins browsers may connect from, any when empty
+    pub allowed_origins: Vec<String>,
+    /// Bytes
+    pub max_message_size: usize,
+    pub log_level: LogLevel,
+    pub log_format: LogFormat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record_dir: Option<PathBuf>,
+    pub web_dir: PathBuf,
+    pub assets_dir: PathBuf,
+    pub auth: AuthConfig,
+    pub rooms: RoomLimits,
+    /// Plain connections are served when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bind: "localhost:27017".to_string(),
+            allowed_origins: Vec::new(),
+            max_message_size: 1024 * 1024,
+            log_level: LogLevel::default(),
+            log_format: LogFormat::default(),
+            record_dir: None,
+            web_dir: "canvas/dist".into(),
+            assets_dir: "canvas/src/assets".into(),
+            auth: AuthConfig::default(),
+            rooms: RoomLimits::default(),
+            tls: None,
+        }
+    }
+}
+
+/// The config file could not be used
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    /// A setting that must be above zero is zero
+    Zero(&'static str),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, err) => write!(f, "Failed to read {}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => {
+                write!(f, "Invalid config {}: {}", path.display(), err)
+            }
+            ConfigError::Zero(field) => write!(f, "Invalid config: {} must be above 0", field),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Read the config file named on the command line, if any, and apply the
+    /// settings given on the command line and in the environment over it
+    pub fn load(cli: &Cli) -> Result<Self, ConfigError> {
+        let mut config = match &cli.config {
+            Some(path) => {
+                let text =
+                    fs::read_to_string(path).map_err(|err| ConfigError::Read(path.clone(), err))?;
+                toml::from_str(&text).map_err(|err| ConfigError::Parse(path.clone(), err))?
+            }
+            None => Config::default(),
+        };
+        config.apply(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check the limits that stop the relay from working when zero: no
+    /// frame fits in an empty queue or message, and every room would stall
+    fn validate(&self) -> Result<(), ConfigError> {
+        for (field, value) in [
+            ("max_message_size", self.max_message_size as u64),
+            ("rooms.queue_capacity", self.rooms.queue_capacity as u64),
+            ("rooms.stall_timeout", self.rooms.stall_timeout),
+        ] {
+            if value == 0 {
+                return Err(ConfigError::Zero(field));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, cli: &Cli) {
+        fn set<T: Clone>(field: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *field = value.clone();
+            }
+        }
+        fn set_list(field: &mut Vec<String>, values: &[String]) {
+            let values: Vec<String> = values
+                .iter()
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(String::from)
+                .collect();
+            if !values.is_empty() {
+                *field = values;
+            }
+        }
+
+        set(&mut self.bind, &cli.bind);
+        set_list(&mut self.allowed_origins, &cli.allowed_origins);
+        set(&mut self.max_message_size, &cli.max_message_size);
+        set(&mut self.log_level, &cli.log_level);
+        set(&mut self.log_format, &cli.log_format);
+        if cli.record_dir.is_some() {
+            self.record_dir = cli.record_dir.clone();
+        }
+        set(&mut self.web_dir, &cli.web_dir);
+        set(&mut self.assets_dir, &cli.assets_dir);
+        set_list(&mut self.auth.producer_tokens, &cli.producer_tokens);
+        set_list(&mut self.auth.viewer_tokens, &cli.viewer_tokens);
+        set_list(&mut self.auth.team0_tokens, &cli.team0_tokens);
+        set_list(&mut self.auth.team1_tokens, &cli.team1_tokens);
+        set_list(&mut self.auth.admin_tokens, &cli.admin_tokens);
+        set(&mut self.rooms.max_rooms, &cli.max_rooms);
+        set(&mut self.rooms.max_producers, &cli.max_producers);
+        set(&mut self.rooms.max_viewers, &cli.max_viewers);
+        set(&mut self.rooms.queue_capacity, &cli.queue_capacity);
+        set(&mut self.rooms.stall_timeout, &cli.stall_timeout);
+        if let (Some(cert), Some(key)) = (&cli.tls_cert, &cli.tls_key) {
+            self.tls = Some(TlsConfig {
+                cert: cert.clone(),
+                key: key.clone(),
+            });
+        }
+    }
+
+    /// Whether a browser at `origin` may connect. Clients that send no
+    /// `Origin`, i.e. anything but a browser, are always allowed
+    pub fn allows_origin(&self, origin: Option<&str>) -> bool {
+        match origin {
+            Some(origin) if !self.allowed_origins.is_empty() => self
+                .allowed_origins
+                .iter()
+                .any(|allowed| allowed.trim_end_matches('/') == origin),
+            _ => true,
+        }
+    }
+
+    /// The configuration with the tokens masked, to log at startup
+    pub fn masked(&self) -> Config {
+        let mask = |tokens: &[String]| vec!["********".to_string(); tokens.len()];
+        let mut shown = self.clone();
+        shown.auth.producer_tokens = mask(&self.auth.producer_tokens);
+        shown.auth.viewer_tokens = mask(&self.auth.viewer_tokens);
+        shown.auth.team0_tokens = mask(&self.auth.team0_tokens);
+        shown.auth.team1_tokens = mask(&self.auth.team1_tokens);
+        shown.auth.admin_tokens = mask(&self.auth.admin_tokens);
+        shown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+    use std::ffi::OsStr;
+
+    /// Parse a command line, ignoring the `RADAR_*` variables of the
+    /// machine running the tests
+    fn parse(args: &[&OsStr]) -> Cli {
+        let command = Cli::command().mut_args(|arg| arg.env(None));
+        Cli::from_arg_matches(&command.get_matches_from(args)).unwrap()
+    }
+
+    /// Load a config file, then apply the command line `args` over it
+    fn load(file: &str, args: &[&str]) -> Result<Config, ConfigError> {
+        let path = std::env::temp_dir().join(format!(
+            "radar-config-{}-{:?}.toml",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        fs::write(&path, file).unwrap();
+        let mut command_line = vec!["server".as_ref(), "--config".as_ref(), path.as_os_str()];
+        command_line.extend(args.iter().map(OsStr::new));
+        let config = Config::load(&parse(&command_line));
+        fs::remove_file(&path).unwrap();
+        config
+    }
+
+    #[test]
+    fn command_line_wins_over_file_which_wins_over_defaults() {
+        let config = load(
+            "bind = \"0.0.0.0:9000\"\n\
+             log_level = \"debug\"\n\
+             [rooms]\n\
+             max_viewers = 8\n",
+            &["--max-viewers", "16"],
+        )
+        .unwrap();
+
+        let defaults = Config::default();
+        assert_eq!(config.rooms.max_viewers, 16);
+        assert_eq!(config.bind, "0.0.0.0:9000");
+        assert_eq!(config.log_level, LogLevel::Debug);
+        assert_eq!(config.rooms.max_rooms, defaults.rooms.max_rooms);
+        assert_eq!(config.max_message_size, defaults.max_message_size);
+    }
+
+    #[test]
+    fn zero_limits_are_refused() {
+        let zero = |file, args: &[&str]| match load(file, args) {
+            Err(ConfigError::Zero(field)) => field,
+            result => panic!("expected a zero limit, got {:?}", result),
+        };
+        assert_eq!(zero("max_message_size = 0", &[]), "max_message_size");
+        assert_eq!(
+            zero("[rooms]\nqueue_capacity = 0", &[]),
+            "rooms.queue_capacity"
+        );
+        assert_eq!(zero("", &["--stall-timeout", "0"]), "rooms.stall_timeout");
+        // The command line can fix a zero in the file
+        assert!(load("max_message_size = 0", &["--max-message-size", "1024"]).is_ok());
+    }
+}
@@ -0,0 +1,78 @@
+use crate::auth::Auth;
+use crate::wire;
+use futures_util::{SinkExt, StreamExt};
+use protocol::{Encoding, HandshakeError, Hello, HelloReply, Role, Team};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::WebSocketStream;
+use tracing::warn;
+
+/// How long a peer has to send its hello once the WebSocket is open
+pub const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wait for the peer's hello and reply with the accepted options
+/// # Arguments
+/// * `ws_stream` - The newly opened WebSocket
+/// * `negotiated` - The encoding picked from `Sec-WebSocket-Protocol`
+/// * `auth` - The tokens accepted for each role
+/// * `admit` - Whether the room has space for a peer in the role it asked for
+/// # Returns
+/// * `Ok((Role, Encoding, Option<Team>))` - The peer's role, the encoding to
+///   send it and the team whose view a viewer is sent
+/// * `Err(HandshakeError)` - The peer was refused and the socket closed
+pub async fn hello<S: AsyncRead + AsyncWrite + Unpin>(
+    ws_stream: &mut WebSocketStream<S>,
+    negotiated: Encoding,
+    auth: &Auth,
+    admit: impl FnOnce(Role) -> Result<(), HandshakeError>,
+) -> Result<(Role, Encoding, Option<Team>), HandshakeError> {
+    let (hello, reply_encoding) = match ws_stream.next().await {
+        Some(Ok(msg)) => (wire::decode::<Hello>(&msg), wire::encoding_of(&msg)),
+        _ => return Err(HandshakeError::ExpectedHello),
+    };
+    let accepted = hello
+        .ok_or(HandshakeError::ExpectedHello)
+        .and_then(|hello| {
+            let encoding = hello.accept(negotiated)?;
+            let team = auth.check(hello.role, hello.token.as_deref(), hello.team)?;
+            admit(hello.role)?;
+            Ok((hello.role, encoding, team))
+        });
+
+    match accepted {
+        Ok((role, encoding, team)) => {
+            let reply = HelloReply::Welcome {
+                version: protocol::PROTOCOL_VERSION,
+                role,
+                encoding,
+                team,
+            };
+            if let Some(welcome) = wire::encode(&reply, encoding) {
+                if let Err(err) = ws_stream.send(welcome).await {
+                    warn!(%err, "Error sending welcome");
+                }
+            }
+            Ok((role, encoding, team))
+        }
+        Err(error) => {
+            let reply = HelloReply::Rejected {
+                error: error.clone(),
+            };
+            if let Some(rejection) = wire::encode(&reply, reply_encoding) {
+                if let Err(err) = ws_stream.send(rejection).await {
+                    warn!(%err, "Error sending rejection");
+                }
+            }
+            let close = CloseFrame {
+                code: CloseCode::from(error.close_code()),
+                reason: error.to_string().into(),
+            };
+            if let Err(err) = ws_stream.close(Some(close)).await {
+                warn!(%err, "Error closing connection");
+            }
+            Err(error)
+        }
+    }
+}
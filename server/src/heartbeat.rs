@@ -0,0 +1,73 @@
+use crate::lock::lock;
+use futures_util::{stream, Stream};
+use std::{sync::Mutex, time::Duration};
+use tokio::time::{interval_at, sleep, Instant};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+/// How often peers are pinged
+pub const PING_INTERVAL: Duration = Duration::from_secs(15);
+/// How long a peer has to answer a ping
+pub const PONG_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a peer may stay silent, pongs included, before it is dropped
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+struct State {
+    last_seen: Instant,
+    /// When the unanswered ping was sent
+    ping_sent: Option<Instant>,
+}
+
+/// Tracks whether a peer is still there, so that half-open connections are
+/// dropped instead of being sent frames forever
+pub struct Heartbeat {
+    state: Mutex<State>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Heartbeat {
+            state: Mutex::new(State {
+                last_seen: Instant::now(),
+                ping_sent: None,
+            }),
+        }
+    }
+
+    /// Note a message received from the peer
+    pub fn received(&self, msg: &Message) {
+        let mut state = lock(&self.state);
+        state.last_seen = Instant::now();
+        if msg.is_pong() {
+            state.ping_sent = None;
+        }
+    }
+
+    /// Pings to send to the peer every [`PING_INTERVAL`]
+    pub fn pings(&self) -> impl Stream<Item = Message> + '_ {
+        let ticks = interval_at(Instant::now() + PING_INTERVAL, PING_INTERVAL);
+        stream::unfold(ticks, move |mut ticks| async move {
+            ticks.tick().await;
+            lock(&self.state).ping_sent.get_or_insert_with(Instant::now);
+            Some((Message::Ping(Default::default()), ticks))
+        })
+    }
+
+    /// Wait until the peer misses a pong or stays idle for too long
+    /// # Returns
+    /// * `String` - Why the peer is considered gone
+    pub async fn expired(&self) -> String {
+        loop {
+            sleep(Duration::from_secs(1)).await;
+            let state = lock(&self.state);
+            if state
+                .ping_sent
+                .is_some_and(|sent| sent.elapsed() >= PONG_TIMEOUT)
+            {
+                return format!("no pong within {}s", PONG_TIMEOUT.as_secs());
+            }
+            if state.last_seen.elapsed() >= IDLE_TIMEOUT {
+                return format!("idle for {}s", IDLE_TIMEOUT.as_secs());
+            }
+        }
+    }
+}
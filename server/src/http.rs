@@ -0,0 +1,270 @@
+use std::{
+    io::{self, ErrorKind},
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    fs,
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    time::timeout,
+};
+
+/// Largest request head accepted, in bytes
+const MAX_HEAD_LEN: usize = 8 * 1024;
+/// How long a client may take to send its request head
+const HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Files served over plain HTTP next to the WebSocket relay
+#[derive(Debug, Clone)]
+pub struct StaticFiles {
+    root: PathBuf,
+    assets: PathBuf,
+}
+
+impl StaticFiles {
+    pub fn new(root: PathBuf, assets: PathBuf) -> Self {
+        StaticFiles { root, assets }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Find the file for a request path, `/` being `index.html`. Images
+    /// (`/images/{name}`) missing from the build are looked up in every
+    /// directory of the assets
+    /// # Returns
+    /// * `None` - If the path leaves the served directories or no file matches
+    async fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let relative = Path::new(path.trim_start_matches('/'));
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+        let file = if relative.as_os_str().is_empty() {
+            self.root.join("index.html")
+        } else {
+            self.root.join(relative)
+        };
+        if fs::metadata(&file).await.is_ok_and(|meta| meta.is_file()) {
+            return Some(file);
+        }
+
+        let name = relative.strip_prefix("images").ok()?;
+        let mut dirs = fs::read_dir(&self.assets).await.ok()?;
+        while let Ok(Some(dir)) = dirs.next_entry().await {
+            let file = dir.path().join(name);
+            if fs::metadata(&file).await.is_ok_and(|meta| meta.is_file()) {
+                return Some(file);
+            }
+        }
+        None
+    }
+}
+
+/// Read the request head. The bytes read are kept so that WebSocket upgrades
+/// can still be handed to tungstenite, which reads the head again
+/// # Returns
+/// * `Ok((String, Rewind<S>))` - The request line and headers, and the stream
+///   that replays them
+/// * `Err(io::Error)` - If the client closed the connection, sent more than
+///   8KiB, sent invalid UTF-8 or took too long
+pub async fn read_head<S>(mut stream: S) -> io::Result<(String, Rewind<S>)>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut buffer = Vec::new();
+    let read = async {
+        let mut chunk = [0; 1024];
+        loop {
+            let len = stream.read(&mut chunk).await?;
+            if len == 0 {
+                return Err(ErrorKind::UnexpectedEof.into());
+            }
+            buffer.extend_from_slice(&chunk[..len]);
+            if let Some(end) = find_head_end(&buffer) {
+                return String::from_utf8(buffer[..end].to_vec()).map_err(|_| {
+                    io::Error::new(ErrorKind::InvalidData, "request head is not UTF-8")
+                });
+            }
+            if buffer.len() >= MAX_HEAD_LEN {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "request head too large",
+                ));
+            }
+        }
+    };
+    let head = timeout(HEAD_TIMEOUT, read)
+        .await
+        .map_err(|_| io::Error::from(ErrorKind::TimedOut))??;
+    Ok((head, Rewind::new(buffer, stream)))
+}
+
+/// A stream that first replays bytes already read from it
+pub struct Rewind<S> {
+    buffered: Vec<u8>,
+    position: usize,
+    inner: S,
+}
+
+impl<S> Rewind<S> {
+    fn new(buffered: Vec<u8>, inner: S) -> Self {
+        Rewind {
+            buffered,
+            position: 0,
+            inner,
+        }
+    }
+
+    /// Drop the first `len` replayed bytes, e.g. a request head already handled
+    fn skip(&mut self, len: usize) {
+        self.position = (self.position + len).min(self.buffered.len());
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Rewind<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if this.position < this.buffered.len() {
+            let len = buf.remaining().min(this.buffered.len() - this.position);
+            buf.put_slice(&this.buffered[this.position..this.position + len]);
+            this.position += len;
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Rewind<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+fn find_head_end(bytes: &[u8]) -> Option<usize> {
+    bytes
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|position| position + 4)
+}
+
+/// Whether a request head asks to upgrade to a WebSocket
+pub fn is_websocket_upgrade(head: &str) -> bool {
+    header(head, "upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+/// The value of the first header named `name` in a request head
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+/// The token of an `Authorization: Bearer ...` header
+pub fn bearer_token(head: &str) -> Option<&str> {
+    let (scheme, token) = header(head, "authorization")?.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+/// Answer a plain HTTP request with a static file and close the connection
+/// # Arguments
+/// * `stream` - The connection returned by [`read_head`]
+/// * `head` - The request head returned by [`read_head`]
+/// * `files` - The files to serve
+/// * `pages` - Pages generated by the server, e.g. `/status`, looked up by
+///   path before the files. Returns the status, content type and body
+/// # Returns
+/// * `Ok(u16)` - The status sent
+pub async fn serve<S>(
+    mut stream: Rewind<S>,
+    head: &str,
+    files: &StaticFiles,
+    pages: impl FnOnce(&str) -> Option<(u16, &'static str, Vec<u8>)>,
+) -> io::Result<u16>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.skip(head.len());
+
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let target = request_line.next().unwrap_or_default();
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+
+    let (status, content_type, body) = match method {
+        "GET" | "HEAD" => match pages(path) {
+            Some(page) => page,
+            None => match files.resolve(path).await {
+                Some(file) => match fs::read(&file).await {
+                    Ok(body) => (200, content_type(&file), body),
+                    Err(_) => (500, "text/plain", b"Internal Server Error".to_vec()),
+                },
+                None => (404, "text/plain", b"Not Found".to_vec()),
+            },
+        },
+        _ => (405, "text/plain", b"Method Not Allowed".to_vec()),
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason(status),
+        content_type,
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    if method != "HEAD" {
+        stream.write_all(&body).await?;
+    }
+    stream.shutdown().await?;
+    Ok(status)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+/// The `Content-Type` of a file, by extension. Browsers only compile
+/// WebAssembly streamed as `application/wasm`
+fn content_type(file: &Path) -> &'static str {
+    match file.extension().and_then(|extension| extension.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript",
+        Some("wasm") => "application/wasm",
+        Some("css") => "text/css",
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        Some("jpg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("ico") => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
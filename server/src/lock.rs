@@ -0,0 +1,9 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Lock a mutex shared between connections. A connection task that panicked
+/// while holding it must not take the other connections down with it, so
+/// poisoning is ignored: every update made under these locks leaves the
+/// state consistent
+pub fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
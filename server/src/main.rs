@@ -1,30 +1,92 @@
-use futures_channel::mpsc::{unbounded, UnboundedSender};
-use futures_util::{future, pin_mut, stream::TryStreamExt, StreamExt};
+use auth::Auth;
+use clap::Parser;
+use config::{Cli, Config, LogFormat};
+use futures_util::{future, stream, stream::TryStreamExt, StreamExt};
+use heartbeat::Heartbeat;
+use http::StaticFiles;
+use lock::lock;
+use metrics::Status;
+use protocol::{
+    room_from_path, Control, Encoding, Frame, FrameError, HandshakeError, Notice, Role, Team,
+    DEFAULT_ROOM, SUBPROTOCOL_HEADER,
+};
+use recorder::Recorder;
+use replay::Replay;
+use room::{Peer, Room, Rooms};
 use std::{
     collections::HashMap,
-    env,
     io::Error as IoError,
     net::SocketAddr,
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::{TcpListener, TcpStream};
-use tokio_tungstenite::tungstenite::protocol::Message;
-type Tx = UnboundedSender<Message>;
-type PeerMap = Arc<Mutex<HashMap<SocketAddr, Tx>>>;
+use tokio::sync::Notify;
+use tokio::time::timeout;
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::tungstenite::error::{CapacityError, Error as WsError};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{header::ORIGIN, HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, Message, WebSocketConfig};
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::{filter::Targets, prelude::*};
+
+mod auth;
+mod config;
+mod handshake;
+mod heartbeat;
+mod http;
+mod lock;
+mod metrics;
+mod queue;
+mod recorder;
+mod replay;
+mod room;
+mod throttle;
+mod tls;
+mod wire;
+
+/// How long a peer refused for an oversized message is given to receive the
+/// reason before the connection is dropped
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long to wait before accepting again after accepting failed, e.g.
+/// while the process is out of file descriptors
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Clone)]
 struct Server {
-    clients: Arc<Mutex<HashMap<SocketAddr, Tx>>>,
+    rooms: Rooms,
+    auth: Arc<Auth>,
+    recorder: Option<Arc<Recorder>>,
+    files: Arc<StaticFiles>,
+    config: Arc<Config>,
+    /// Encrypts connections when a certificate is configured
+    tls: Option<TlsAcceptor>,
+    started: Instant,
+    /// The room a recording is served in, producers may not join it
+    replay_room: Option<String>,
 }
 /// Taken from tokio-tungstenite examples
 /// url: `<https://github.com/snapview/tokio-tungstenite>`
 /// # Summary
-/// Host a websocket server on the given address
+/// Host a websocket server on the given address. Requests that are not
+/// WebSocket upgrades are answered with the canvas files
 /// # Arguments
 /// * `addr` - The address to host the server on
 /// # Example
 /// ```
 /// use server::Server;
 /// let server = Server {
-///    clients: Arc::new(Mutex::new(HashMap::new())),
+///    rooms: Arc::new(Mutex::new(HashMap::new())),
+///    auth: Arc::new(Auth::default()),
+///    recorder: None,
+///    files: Arc::new(StaticFiles::new("canvas/dist".into(), "canvas/src/assets".into())),
+///    config: Arc::new(Config::default()),
+///    tls: None,
+///    started: Instant::now(),
+///    replay_room: None,
 /// };
 /// ```
 /// # Panics
@@ -33,73 +95,564 @@ struct Server {
 /// * `Ok(())` - If the server successfully binds to the given address
 /// * `Err(IoError)` - If the server fails to bind to the given address
 impl Server {
-    pub async fn handle_connection(peer_map: PeerMap, raw_stream: TcpStream, addr: SocketAddr) {
-        println!("Incoming TCP connection from: {}", addr);
+    /// Complete the TLS handshake, if enabled, and handle the connection
+    pub async fn accept(self, stream: TcpStream, addr: SocketAddr) {
+        let Some(acceptor) = self.tls.clone() else {
+            return self.handle_connection(stream, addr).await;
+        };
+        match timeout(tls::TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+            Ok(Ok(stream)) => self.handle_connection(stream, addr).await,
+            Ok(Err(err)) => warn!(%addr, %err, "Refused connection"),
+            Err(_) => warn!(
+                %addr,
+                timeout = tls::TLS_HANDSHAKE_TIMEOUT.as_secs(),
+                "Refused connection: no TLS handshake in time"
+            ),
+        }
+    }
+
+    pub async fn handle_connection<S>(self, stream: S, addr: SocketAddr)
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let Server {
+            rooms,
+            auth,
+            recorder,
+            files,
+            config,
+            tls: _,
+            started,
+            replay_room,
+        } = self;
+
+        // The canvas and the relay share one port
+        let (head, raw_stream) = match http::read_head(stream).await {
+            Ok(read) => read,
+            Err(err) => {
+                warn!(%addr, %err, "Refused connection");
+                return;
+            }
+        };
+        if !http::is_websocket_upgrade(&head) {
+            let request = head.lines().next().unwrap_or_default().to_string();
+            let admin = auth.allows_admin(addr.ip(), http::bearer_token(&head));
+            let pages = |path: &str| match path {
+                "/metrics" | "/status" if !admin => {
+                    Some((401, "text/plain", b"Unauthorized".to_vec()))
+                }
+                "/metrics" => Some((
+                    200,
+                    "text/plain; version=0.0.4",
+                    Status::collect(&rooms, started)
+                        .to_prometheus()
+                        .into_bytes(),
+                )),
+                "/status" => Some((
+                    200,
+                    "application/json",
+                    Status::collect(&rooms, started).to_json().into_bytes(),
+                )),
+                _ => None,
+            };
+            match http::serve(raw_stream, &head, &files, pages).await {
+                Ok(status) => info!(%addr, %request, status, "HTTP request"),
+                Err(err) => warn!(%addr, %request, %err, "Failed to answer HTTP request"),
+            }
+            return;
+        }
+        debug!(%addr, "Incoming WebSocket connection");
 
-        let ws_stream = tokio_tungstenite::accept_async(raw_stream)
-            .await
-            .expect("Error during the websocket handshake occurred");
-        println!("WebSocket connection established: {}", addr);
+        let mut encoding = Encoding::Json;
+        let mut room = String::new();
+        // The error type is fixed by tungstenite's `Callback`
+        #[allow(clippy::result_large_err)]
+        let negotiate = |request: &Request, mut response: Response| {
+            let refuse = |status, reason: String| {
+                let mut error = ErrorResponse::new(Some(reason));
+                *error.status_mut() = status;
+                Err(error)
+            };
+            // Browsers on other sites must not reach the relay
+            let origin = request
+                .headers()
+                .get(ORIGIN)
+                .and_then(|value| value.to_str().ok());
+            if !config.allows_origin(origin) {
+                return refuse(
+                    StatusCode::FORBIDDEN,
+                    format!("Origin {} is not allowed", origin.unwrap_or_default()),
+                );
+            }
+            // The request path selects the match, e.g. `/match/{id}`
+            let path = request.uri().path();
+            match room_from_path(path) {
+                Some(id) => room = id.to_string(),
+                None => return refuse(StatusCode::NOT_FOUND, format!("No room at {}", path)),
+            }
+            {
+                let rooms = lock(&rooms);
+                if !rooms.contains_key(&room) && rooms.len() >= config.rooms.max_rooms {
+                    return refuse(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "Too many rooms are open".to_string(),
+                    );
+                }
+            }
+            // Legacy clients that offer no subprotocol keep receiving JSON
+            let offered = request
+                .headers()
+                .get(SUBPROTOCOL_HEADER)
+                .and_then(|value| value.to_str().ok());
+            if let Some(negotiated) = offered.and_then(Encoding::negotiate) {
+                encoding = negotiated;
+                response.headers_mut().insert(
+                    SUBPROTOCOL_HEADER,
+                    HeaderValue::from_static(negotiated.subprotocol()),
+                );
+            }
+            Ok(response)
+        };
+        let ws_config = WebSocketConfig::default()
+            .max_message_size(Some(config.max_message_size))
+            .max_frame_size(Some(config.max_message_size));
+        let accepted =
+            tokio_tungstenite::accept_hdr_async_with_config(raw_stream, negotiate, Some(ws_config))
+                .await;
+        let mut ws_stream = match accepted {
+            Ok(ws_stream) => ws_stream,
+            Err(err) => {
+                warn!(%addr, %err, "Refused connection");
+                return;
+            }
+        };
+        debug!(%addr, "WebSocket connection established");
+
+        // Rooms only take as many peers in each role as configured
+        let admit = |role| {
+            if role == Role::Producer && replay_room.as_ref() == Some(&room) {
+                return Err(HandshakeError::Replaying);
+            }
+            let limit = match role {
+                Role::Producer => config.rooms.max_producers,
+                Role::Viewer => config.rooms.max_viewers,
+            };
+            let count = lock(&rooms).get(&room).map_or(0, |peers| peers.count(role));
+            if count < limit {
+                Ok(())
+            } else {
+                Err(HandshakeError::RoomFull { role })
+            }
+        };
+        let hello = handshake::hello(&mut ws_stream, encoding, &auth, admit);
+        let (role, encoding, team) = match timeout(handshake::HELLO_TIMEOUT, hello).await {
+            Ok(Ok(accepted)) => accepted,
+            Ok(Err(err)) => {
+                warn!(%addr, %room, %err, "Refused peer");
+                return;
+            }
+            Err(_) => {
+                warn!(
+                    %addr,
+                    %room,
+                    timeout = handshake::HELLO_TIMEOUT.as_secs(),
+                    "Refused peer: no hello in time"
+                );
+                return;
+            }
+        };
+        info!(
+            %addr,
+            %room,
+            %role,
+            subprotocol = encoding.subprotocol(),
+            team = ?team.map(|team| team.index()),
+            "Peer joined"
+        );
 
-        // Insert the write part of this peer to the peer map.
-        let (tx, rx) = unbounded();
-        peer_map.lock().unwrap().insert(addr, tx);
+        // Insert the write part of this peer to its room.
+        let (tx, rx) = queue::channel(config.rooms.queue_capacity);
+        lock(&rooms).entry(room.clone()).or_default().join(
+            addr,
+            Peer {
+                tx,
+                role,
+                encoding,
+                team,
+                throttle: None,
+            },
+        );
+        let _membership = Membership {
+            rooms: rooms.clone(),
+            room: room.clone(),
+            addr,
+            recorder: recorder.clone(),
+        };
 
         let (outgoing, incoming) = ws_stream.split();
+        let heartbeat = Heartbeat::new();
 
+        // After a refused frame the producer's deltas cannot be applied, they
+        // are dropped until it sends a valid keyframe
+        let mut quarantined = false;
+        // Wakes the pacing of a viewer that changed its frame rate
+        let frame_rate_set = Notify::new();
         let broadcast_incoming = incoming.try_for_each(|msg| {
-            println!(
-                "Received a message from {}: {}",
-                addr,
-                msg.to_text().unwrap()
-            );
-            let peers = peer_map.lock().unwrap();
-
-            // We want to broadcast the message to everyone except ourselves.
-            let broadcast_recipients = peers
-                .iter()
-                .filter(|(peer_addr, _)| peer_addr != &&addr)
-                .map(|(_, ws_sink)| ws_sink);
+            heartbeat.received(&msg);
+            if !(msg.is_text() || msg.is_binary()) {
+                return future::ok(());
+            }
+            let mut rooms = lock(&rooms);
+            let Some(peers) = rooms.get_mut(&room) else {
+                return future::ok(());
+            };
+            peers.traffic.received(msg.len());
 
-            for recp in broadcast_recipients {
-                recp.unbounded_send(msg.clone()).unwrap();
+            // Only producers send game data, viewers may only send control messages
+            match role {
+                Role::Producer => match check_frame(&msg, peers, quarantined) {
+                    Ok(frame) => {
+                        quarantined = false;
+                        match &frame {
+                            Frame::Keyframe { seq, data } => debug!(
+                                %addr,
+                                seq,
+                                players = data.players.len(),
+                                "Received keyframe"
+                            ),
+                            Frame::Delta { seq, .. } => debug!(%addr, seq, "Received delta"),
+                        }
+                        // Frames the room could not apply are neither relayed
+                        // nor recorded
+                        if peers.publish(addr, &frame).is_ok() {
+                            if let Some(recorder) = &recorder {
+                                recorder.record(&room, &frame);
+                            }
+                        }
+                    }
+                    Err((seq, error)) => {
+                        peers.traffic.rejected();
+                        if error == FrameError::Quarantined {
+                            debug!(%addr, ?seq, "Dropped a delta from a quarantined producer");
+                        } else {
+                            warn!(%addr, ?seq, %error, "Rejected frame");
+                            peers.reply(&addr, &Notice::Rejected { seq, error });
+                        }
+                        if !quarantined {
+                            quarantined = true;
+                            peers.reply(&addr, &Control::RequestKeyframe);
+                        }
+                    }
+                },
+                Role::Viewer => match wire::try_decode::<Control>(&msg) {
+                    Ok(Control::SetFrameRate { fps }) => {
+                        peers.set_frame_rate(&addr, fps);
+                        frame_rate_set.notify_one();
+                    }
+                    Ok(control) => {
+                        debug!(%addr, ?control, "Received control");
+                        peers.broadcast(addr, role, &control);
+                    }
+                    Err(detail) => {
+                        warn!(%addr, %role, %detail, "Ignored a message");
+                        let error = FrameError::Malformed { detail };
+                        peers.reply(&addr, &Notice::Rejected { seq: None, error });
+                    }
+                },
             }
 
             future::ok(())
         });
 
-        let receive_from_others = rx.map(Ok).forward(outgoing);
+        let queued = stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|msg| (Ok(msg), rx))
+        });
+        let receive_from_others =
+            stream::select(queued, heartbeat.pings().map(Ok)).forward(outgoing);
+
+        tokio::pin!(receive_from_others);
 
-        pin_mut!(broadcast_incoming, receive_from_others);
-        future::select(broadcast_incoming, receive_from_others).await;
+        // Frames held back for a throttled viewer are sent by its own task
+        // once they are due, other peers never take the lock for it
+        let pace = async {
+            loop {
+                let next_check = lock(&rooms)
+                    .get_mut(&room)
+                    .and_then(|peers| peers.pace(&addr, Instant::now()));
+                match next_check {
+                    Some(next_check) => tokio::select! {
+                        _ = tokio::time::sleep_until(next_check.into()) => {}
+                        _ = frame_rate_set.notified() => {}
+                    },
+                    None => frame_rate_set.notified().await,
+                }
+            }
+        };
 
-        println!("{} disconnected", &addr);
-        peer_map.lock().unwrap().remove(&addr);
+        let too_large = tokio::select! {
+            result = broadcast_incoming => match result {
+                Err(WsError::Capacity(CapacityError::MessageTooLong { size, max_size })) => {
+                    Some(FrameError::TooLarge { size, max: max_size })
+                }
+                Err(err) => {
+                    warn!(%addr, %err, "Connection error");
+                    None
+                }
+                Ok(()) => None,
+            },
+            result = &mut receive_from_others => {
+                if let Err(err) = result {
+                    warn!(%addr, %err, "Connection error");
+                }
+                None
+            }
+            reason = heartbeat.expired() => {
+                info!(%addr, %reason, "Dropped peer");
+                None
+            }
+            _ = pace => None,
+        };
+
+        // The rest of an oversized message is not read, tell the peer why
+        // before closing the connection
+        if let Some(error) = too_large {
+            warn!(%addr, %error, "Rejected message");
+            if let Some(peers) = lock(&rooms).get_mut(&room) {
+                peers.traffic.rejected();
+                let close = CloseFrame {
+                    code: CloseCode::Size,
+                    reason: error.to_string().into(),
+                };
+                peers.reply(&addr, &Notice::Rejected { seq: None, error });
+                peers.send_raw(&addr, Message::Close(Some(close)));
+            }
+            let _ = timeout(CLOSE_TIMEOUT, receive_from_others).await;
+        }
     }
 }
 
+/// Check a producer's message before it is relayed
+/// # Arguments
+/// * `msg` - The message received
+/// * `room` - The producer's room, holding the state the frame applies to
+/// * `quarantined` - Whether the producer sent an invalid frame since its
+///   last valid keyframe
+/// # Returns
+/// * `Err((Option<u64>, FrameError))` - The sequence number of the refused
+///   frame, if it could be read, and why it was refused
+fn check_frame(
+    msg: &Message,
+    room: &Room,
+    quarantined: bool,
+) -> Result<Frame, (Option<u64>, FrameError)> {
+    let frame = wire::try_decode::<Frame>(msg)
+        .map_err(|detail| (None, FrameError::Malformed { detail }))?;
+    let seq = Some(frame.seq());
+    if quarantined && !frame.is_keyframe() {
+        return Err((seq, FrameError::Quarantined));
+    }
+    room.validate(&frame).map_err(|err| (seq, err))?;
+    Ok(frame)
+}
+
+/// A peer's place in its room, given up when the connection ends, even if
+/// its task panics
+struct Membership {
+    rooms: Rooms,
+    room: String,
+    addr: SocketAddr,
+    recorder: Option<Arc<Recorder>>,
+}
+
+impl Drop for Membership {
+    fn drop(&mut self) {
+        let Membership {
+            rooms,
+            room,
+            addr,
+            recorder,
+        } = self;
+
+        let mut rooms = lock(rooms);
+        if let Some(peers) = rooms.get_mut(room) {
+            if let Some(peer) = peers.leave(addr) {
+                let lag = peer.lag();
+                info!(
+                    %addr,
+                    %room,
+                    queued = lag.queued,
+                    dropped = lag.dropped,
+                    "Peer disconnected"
+                );
+            }
+            if peers.is_empty() {
+                rooms.remove(room);
+                if let Some(recorder) = recorder {
+                    recorder.close(room);
+                }
+                info!(%room, "Closed room");
+            }
+        }
+    }
+}
+
+/// Wait for Ctrl-C or, on Unix, the SIGTERM sent by `kill`, `docker stop`
+/// and systemd
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(err) => warn!(%err, "Failed to listen for SIGTERM"),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
 #[tokio::main]
 async fn main() -> Result<(), IoError> {
-    let addr = env::args()
-        .nth(1)
-        .unwrap_or_else(|| "localhost:27017".to_string());
+    let cli = Cli::parse();
+    let config = match Config::load(&cli) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    // The level applies to the relay, its dependencies only log warnings
+    let filter = Targets::new()
+        .with_target(
+            env!("CARGO_CRATE_NAME"),
+            tracing::Level::from(config.log_level),
+        )
+        .with_default(tracing::Level::WARN);
+    let layer = tracing_subscriber::fmt::layer().with_target(false);
+    let layer = match config.log_format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    };
+    tracing_subscriber::registry()
+        .with(layer)
+        .with(filter)
+        .init();
+    info!(config = ?config.masked(), "Effective configuration");
 
+    let recorder = match &config.record_dir {
+        Some(dir) => match Recorder::start(dir.clone()) {
+            Ok(recorder) => Some(Arc::new(recorder)),
+            Err(err) => {
+                error!(dir = %dir.display(), %err, "Failed to create the recording directory");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let tls = match &config.tls {
+        Some(tls) => match tls::acceptor(&tls.cert, &tls.key) {
+            Ok(acceptor) => Some(acceptor),
+            Err(err) => {
+                error!(%err, "Failed to load the TLS certificate");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
     let server = Server {
-        clients: Arc::new(Mutex::new(HashMap::new())),
+        rooms: Arc::new(Mutex::new(HashMap::new())),
+        auth: Arc::new(
+            Auth::new(
+                config.auth.producer_tokens.clone(),
+                config.auth.viewer_tokens.clone(),
+            )
+            .with_team_tokens(Team::Attackers, config.auth.team0_tokens.clone())
+            .with_team_tokens(Team::Defenders, config.auth.team1_tokens.clone())
+            .with_admin_tokens(config.auth.admin_tokens.clone()),
+        ),
+        recorder,
+        files: Arc::new(StaticFiles::new(
+            config.web_dir.clone(),
+            config.assets_dir.clone(),
+        )),
+        config: Arc::new(config),
+        tls,
+        started: Instant::now(),
+        replay_room: cli.replay.as_ref().map(|_| DEFAULT_ROOM.to_string()),
     };
+    for role in [Role::Producer, Role::Viewer] {
+        if !server.auth.is_required(role) {
+            warn!(%role, "Authentication is disabled");
+        }
+    }
+    if server.config.auth.admin_tokens.is_empty() {
+        info!("No admin tokens are set, /status and /metrics are only served to local clients");
+    }
+    if !server.auth.enforces_teams() {
+        warn!("Team views are not enforced without team tokens, any viewer may ask for the full game state");
+    }
+
+    tokio::spawn(metrics::sample_rates(server.rooms.clone()));
+    tokio::spawn(room::watch_stalls(
+        server.rooms.clone(),
+        server.config.rooms.stall_timeout(),
+    ));
+
+    // Serve a recorded match in the default room instead of relaying producers
+    if let Some(path) = &cli.replay {
+        let replay = match Replay::load(path, cli.speed, cli.looping) {
+            Ok(replay) => replay,
+            Err(err) => {
+                error!(path = %path.display(), %err, "Failed to load the replay");
+                std::process::exit(1);
+            }
+        };
+        tokio::spawn(replay.run(server.rooms.clone(), DEFAULT_ROOM.to_string()));
+    }
 
     // Create the event loop and TCP listener we'll accept connections on.
-    let try_socket = TcpListener::bind(&addr).await;
-    let listener = try_socket.expect("Failed to bind");
-    println!("Listening on: {}", addr);
+    let listener = TcpListener::bind(&server.config.bind).await?;
+    let scheme = if server.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    info!(address = %format!("{}://{}", scheme, server.config.bind), "Listening");
+    info!(dir = %server.files.root().display(), "Serving the canvas");
 
     // Let's spawn the handling of each connection in a separate task.
-    while let Ok((stream, addr)) = listener.accept().await {
-        tokio::spawn(Server::handle_connection(
-            server.clients.clone(),
-            stream,
-            addr,
-        ));
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                match accepted {
+                    Ok((stream, addr)) => {
+                        tokio::spawn(server.clone().accept(stream, addr));
+                    }
+                    // Running out of file descriptors or a peer that gave up
+                    // before it was accepted must not stop the server
+                    Err(err) => {
+                        warn!(%err, "Failed to accept a connection");
+                        tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    }
+                }
+            }
+            _ = &mut shutdown => {
+                info!("Shutting down");
+                break;
+            }
+        }
+    }
+
+    // Flush the recordings before exiting
+    if let Some(recorder) = &server.recorder {
+        recorder.shutdown();
     }
 
     Ok(())
@@ -0,0 +1,408 @@
+use crate::lock::lock;
+use crate::room::Rooms;
+use protocol::Role;
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+/// Running totals of a room's traffic
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub messages_in: u64,
+    pub bytes_in: u64,
+    pub messages_out: u64,
+    pub bytes_out: u64,
+    /// Frames viewers missed because they fell behind
+    pub dropped_frames: u64,
+    /// Messages refused because they were too large, malformed or invalid
+    pub rejected: u64,
+}
+
+/// Traffic through a room, per second over the last [`Traffic::sample`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Rates {
+    pub messages_in: f64,
+    pub bytes_in: f64,
+    pub messages_out: f64,
+    pub bytes_out: f64,
+}
+
+/// Counts a room's traffic and how fast it flows
+#[derive(Debug)]
+pub struct Traffic {
+    total: Counters,
+    sampled: Counters,
+    sampled_at: Instant,
+    rates: Rates,
+}
+
+impl Default for Traffic {
+    fn default() -> Self {
+        Traffic {
+            total: Counters::default(),
+            sampled: Counters::default(),
+            sampled_at: Instant::now(),
+            rates: Rates::default(),
+        }
+    }
+}
+
+impl Traffic {
+    /// Count a message received from a peer
+    pub fn received(&mut self, bytes: usize) {
+        self.total.messages_in += 1;
+        self.total.bytes_in += bytes as u64;
+    }
+
+    /// Count a message queued for a peer
+    pub fn sent(&mut self, bytes: usize) {
+        self.total.messages_out += 1;
+        self.total.bytes_out += bytes as u64;
+    }
+
+    pub fn dropped(&mut self, frames: u64) {
+        self.total.dropped_frames += frames;
+    }
+
+    pub fn rejected(&mut self) {
+        self.total.rejected += 1;
+    }
+
+    pub fn total(&self) -> Counters {
+        self.total
+    }
+
+    pub fn rates(&self) -> Rates {
+        self.rates
+    }
+
+    /// Work out the rates since the previous sample
+    pub fn sample(&mut self) {
+        let elapsed = self.sampled_at.elapsed().as_secs_f64();
+        if elapsed <= 0.0 {
+            return;
+        }
+        let rate = |now: u64, before: u64| now.saturating_sub(before) as f64 / elapsed;
+        self.rates = Rates {
+            messages_in: rate(self.total.messages_in, self.sampled.messages_in),
+            bytes_in: rate(self.total.bytes_in, self.sampled.bytes_in),
+            messages_out: rate(self.total.messages_out, self.sampled.messages_out),
+            bytes_out: rate(self.total.bytes_out, self.sampled.bytes_out),
+        };
+        self.sampled = self.total;
+        self.sampled_at = Instant::now();
+    }
+}
+
+/// Update the traffic rates of every room once a second
+pub async fn sample_rates(rooms: Rooms) {
+    let mut ticks = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        ticks.tick().await;
+        for room in lock(&rooms).values_mut() {
+            room.traffic.sample();
+        }
+    }
+}
+
+/// How far behind one peer is, as served by `/status`
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerStatus {
+    pub role: Role,
+    /// Messages waiting to be written to the socket
+    pub queued: usize,
+    /// Frames dropped since the peer connected because it fell behind
+    pub dropped: u64,
+}
+
+/// Health of one room, as served by `/status`
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomStatus {
+    pub producers: usize,
+    pub viewers: usize,
+    /// Per second
+    pub rates: Rates,
+    pub messages_in: u64,
+    pub bytes_in: u64,
+    pub messages_out: u64,
+    pub bytes_out: u64,
+    pub dropped_frames: u64,
+    pub rejected: u64,
+    /// Milliseconds since the last frame, `null` before the first one
+    pub last_frame_age: Option<u64>,
+    pub stalled: bool,
+    /// The peers furthest behind first
+    pub peers: Vec<PeerStatus>,
+}
+
+impl RoomStatus {
+    /// The peers of the room in `role`
+    fn peers(&self, role: Role) -> impl Iterator<Item = &PeerStatus> {
+        self.peers.iter().filter(move |peer| peer.role == role)
+    }
+}
+
+/// Health of the relay, as served by `/status`
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    /// Seconds since the server started
+    pub uptime: u64,
+    pub rooms: BTreeMap<String, RoomStatus>,
+}
+
+impl Status {
+    /// Read the state of every room
+    /// # Arguments
+    /// * `rooms` - The open rooms
+    /// * `started` - When the server started
+    pub fn collect(rooms: &Rooms, started: Instant) -> Self {
+        let rooms = lock(rooms)
+            .iter()
+            .map(|(id, room)| {
+                let total = room.traffic.total();
+                let mut peers: Vec<PeerStatus> = room
+                    .peers()
+                    .map(|(_, peer)| {
+                        let lag = peer.lag();
+                        PeerStatus {
+                            role: peer.role,
+                            queued: lag.queued,
+                            dropped: lag.dropped,
+                        }
+                    })
+                    .collect();
+                peers.sort_by_key(|peer| Reverse((peer.queued, peer.dropped)));
+                let status = RoomStatus {
+                    producers: room.count(Role::Producer),
+                    viewers: room.count(Role::Viewer),
+                    rates: room.traffic.rates(),
+                    messages_in: total.messages_in,
+                    bytes_in: total.bytes_in,
+                    messages_out: total.messages_out,
+                    bytes_out: total.bytes_out,
+                    dropped_frames: total.dropped_frames,
+                    rejected: total.rejected,
+                    last_frame_age: room.last_frame_age().map(|age| age.as_millis() as u64),
+                    stalled: room.is_stalled(),
+                    peers,
+                };
+                (id.clone(), status)
+            })
+            .collect();
+        Status {
+            uptime: started.elapsed().as_secs(),
+            rooms,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// The status in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, values: Vec<(String, String)>| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, value) in values {
+                let _ = writeln!(out, "{}{} {}", name, labels, value);
+            }
+        };
+        let per_room = |value: &dyn Fn(&RoomStatus) -> String| {
+            self.rooms
+                .iter()
+                .map(|(id, room)| (format!("{{room=\"{}\"}}", id), value(room)))
+                .collect::<Vec<_>>()
+        };
+        // Peers come and go, so they are only counted by room and role
+        let per_role = |value: &dyn Fn(&RoomStatus, Role) -> String| {
+            self.rooms
+                .iter()
+                .flat_map(|(id, room)| {
+                    [Role::Producer, Role::Viewer].map(|role| {
+                        let labels = format!("{{room=\"{}\",role=\"{}\"}}", id, role);
+                        (labels, value(room, role))
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        metric(
+            "radar_uptime_seconds",
+            "gauge",
+            "Seconds since the server started",
+            vec![(String::new(), self.uptime.to_string())],
+        );
+        metric(
+            "radar_rooms",
+            "gauge",
+            "Rooms open",
+            vec![(String::new(), self.rooms.len().to_string())],
+        );
+        metric(
+            "radar_peers",
+            "gauge",
+            "Peers connected to a room",
+            per_role(&|room, role| room.peers(role).count().to_string()),
+        );
+        metric(
+            "radar_messages_received_total",
+            "counter",
+            "Messages received from the peers of a room",
+            per_room(&|room| room.messages_in.to_string()),
+        );
+        metric(
+            "radar_bytes_received_total",
+            "counter",
+            "Bytes received from the peers of a room",
+            per_room(&|room| room.bytes_in.to_string()),
+        );
+        metric(
+            "radar_messages_sent_total",
+            "counter",
+            "Messages sent to the peers of a room",
+            per_room(&|room| room.messages_out.to_string()),
+        );
+        metric(
+            "radar_bytes_sent_total",
+            "counter",
+            "Bytes sent to the peers of a room",
+            per_room(&|room| room.bytes_out.to_string()),
+        );
+        metric(
+            "radar_messages_received_per_second",
+            "gauge",
+            "Messages received per second over the last second",
+            per_room(&|room| room.rates.messages_in.to_string()),
+        );
+        metric(
+            "radar_bytes_received_per_second",
+            "gauge",
+            "Bytes received per second over the last second",
+            per_room(&|room| room.rates.bytes_in.to_string()),
+        );
+        metric(
+            "radar_messages_sent_per_second",
+            "gauge",
+            "Messages sent per second over the last second",
+            per_room(&|room| room.rates.messages_out.to_string()),
+        );
+        metric(
+            "radar_bytes_sent_per_second",
+            "gauge",
+            "Bytes sent per second over the last second",
+            per_room(&|room| room.rates.bytes_out.to_string()),
+        );
+        metric(
+            "radar_dropped_frames_total",
+            "counter",
+            "Frames viewers missed because they fell behind",
+            per_room(&|room| room.dropped_frames.to_string()),
+        );
+        metric(
+            "radar_queued_messages",
+            "gauge",
+            "Messages waiting to be written to the sockets of a room's peers",
+            per_role(&|room, role| {
+                room.peers(role)
+                    .map(|peer| peer.queued)
+                    .sum::<usize>()
+                    .to_string()
+            }),
+        );
+        metric(
+            "radar_max_queued_messages",
+            "gauge",
+            "Messages waiting for the peer of a room furthest behind",
+            per_role(&|room, role| {
+                let most = room.peers(role).map(|peer| peer.queued).max();
+                most.unwrap_or_default().to_string()
+            }),
+        );
+        metric(
+            "radar_rejected_messages_total",
+            "counter",
+            "Messages refused because they were too large, malformed or invalid",
+            per_room(&|room| room.rejected.to_string()),
+        );
+        metric(
+            "radar_last_frame_age_seconds",
+            "gauge",
+            "Seconds since the room received a frame",
+            self.rooms
+                .iter()
+                .filter_map(|(id, room)| {
+                    let age = room.last_frame_age? as f64 / 1000.0;
+                    Some((format!("{{room=\"{}\"}}", id), age.to_string()))
+                })
+                .collect(),
+        );
+        metric(
+            "radar_stalled",
+            "gauge",
+            "Whether viewers were told the room's feed stalled",
+            per_room(&|room| u8::from(room.stalled).to_string()),
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(peers: Vec<PeerStatus>) -> RoomStatus {
+        RoomStatus {
+            producers: 1,
+            viewers: peers.len() - 1,
+            rates: Rates::default(),
+            messages_in: 0,
+            bytes_in: 0,
+            messages_out: 0,
+            bytes_out: 0,
+            dropped_frames: 0,
+            rejected: 0,
+            last_frame_age: None,
+            stalled: false,
+            peers,
+        }
+    }
+
+    fn peer(role: Role, queued: usize) -> PeerStatus {
+        PeerStatus {
+            role,
+            queued,
+            dropped: 0,
+        }
+    }
+
+    #[test]
+    fn queues_are_summed_by_room_and_role() {
+        let status = Status {
+            uptime: 0,
+            rooms: BTreeMap::from([(
+                "default".to_string(),
+                room(vec![
+                    peer(Role::Producer, 0),
+                    peer(Role::Viewer, 3),
+                    peer(Role::Viewer, 5),
+                ]),
+            )]),
+        };
+        let metrics = status.to_prometheus();
+        for line in [
+            r#"radar_peers{room="default",role="viewer"} 2"#,
+            r#"radar_queued_messages{room="default",role="viewer"} 8"#,
+            r#"radar_max_queued_messages{room="default",role="viewer"} 5"#,
+            r#"radar_max_queued_messages{room="default",role="producer"} 0"#,
+        ] {
+            assert!(metrics.lines().any(|metric| metric == line), "{}", line);
+        }
+        assert!(!metrics.contains("peer="));
+    }
+}
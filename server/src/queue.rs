@@ -0,0 +1,309 @@
+use crate::lock::lock;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, Message};
+use tracing::warn;
+
+/// Game-state frames a peer may have waiting before it is considered behind
+pub const QUEUE_CAPACITY: usize = 32;
+/// Other messages a peer may have waiting, e.g. events and notices. These
+/// cannot be dropped, so a peer this far behind is disconnected
+pub const MESSAGE_CAPACITY: usize = 1024;
+
+/// The receiving end of the queue is gone, the peer disconnected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+/// How far behind a peer is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Lag {
+    /// Messages waiting to be written to the socket
+    pub queued: usize,
+    /// Frames dropped since the peer connected because it fell behind
+    pub dropped: u64,
+}
+
+struct Queued {
+    message: Message,
+    /// Game-state frames can be dropped, control messages are always delivered
+    is_frame: bool,
+}
+
+struct State {
+    queue: VecDeque<Queued>,
+    frames: usize,
+    dropped: u64,
+    sender_dropped: bool,
+    receiver_dropped: bool,
+    /// Too many messages were waiting, only a close frame is left to send
+    overflowed: bool,
+}
+
+impl State {
+    fn pop(&mut self) -> Option<Message> {
+        let queued = self.queue.pop_front()?;
+        if queued.is_frame {
+            self.frames -= 1;
+        }
+        Some(queued.message)
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+/// Create the outgoing queue of one peer, holding at most `capacity` frames
+pub fn channel(capacity: usize) -> (Sender, Receiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            frames: 0,
+            dropped: 0,
+            sender_dropped: false,
+            receiver_dropped: false,
+            overflowed: false,
+        }),
+        notify: Notify::new(),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+            capacity,
+        },
+        Receiver { shared },
+    )
+}
+
+/// Queues messages for a peer. Once `capacity` frames are waiting, stale
+/// frames are dropped in favour of the newest state. Once
+/// [`MESSAGE_CAPACITY`] other messages are waiting, the queue is replaced by
+/// a close frame and refuses anything more
+pub struct Sender {
+    shared: Arc<Shared>,
+    capacity: usize,
+}
+
+impl Sender {
+    /// Queue a message that must not be dropped, e.g. a control message
+    /// # Returns
+    /// * `Err(Closed)` - If the peer disconnected or fell too far behind
+    pub fn send(&self, message: Message) -> Result<(), Closed> {
+        self.push(message, false, || None).map(|_| ())
+    }
+
+    /// Queue a game-state frame
+    /// # Arguments
+    /// * `message` - The frame
+    /// * `resync` - The full latest state, sent in place of every waiting
+    ///   frame when the queue is full. Without one only the oldest frame is
+    ///   dropped
+    /// # Returns
+    /// * `Ok(u64)` - The number of frames dropped to make room
+    pub fn send_frame(
+        &self,
+        message: Message,
+        resync: impl FnOnce() -> Option<Message>,
+    ) -> Result<u64, Closed> {
+        self.push(message, true, resync)
+    }
+
+    fn push(
+        &self,
+        message: Message,
+        is_frame: bool,
+        resync: impl FnOnce() -> Option<Message>,
+    ) -> Result<u64, Closed> {
+        let mut state = lock(&self.shared.state);
+        if state.receiver_dropped || state.overflowed {
+            return Err(Closed);
+        }
+        if !is_frame && state.queue.len() - state.frames >= MESSAGE_CAPACITY {
+            warn!(
+                queued = state.queue.len(),
+                "Peer fell too far behind, closing the connection"
+            );
+            state.dropped += state.frames as u64;
+            state.frames = 0;
+            state.queue.clear();
+            state.overflowed = true;
+            let close = CloseFrame {
+                code: CloseCode::Again,
+                reason: "Too far behind".into(),
+            };
+            state.queue.push_back(Queued {
+                message: Message::Close(Some(close)),
+                is_frame: false,
+            });
+            drop(state);
+            self.shared.notify.notify_one();
+            return Err(Closed);
+        }
+        let mut dropped = 0;
+        let mut message = message;
+        if is_frame && state.frames >= self.capacity {
+            match resync() {
+                Some(keyframe) => {
+                    state.queue.retain(|queued| !queued.is_frame);
+                    dropped = state.frames as u64;
+                    state.frames = 0;
+                    message = keyframe;
+                }
+                None => {
+                    if let Some(oldest) = state.queue.iter().position(|queued| queued.is_frame) {
+                        state.queue.remove(oldest);
+                        state.frames -= 1;
+                        dropped = 1;
+                    }
+                }
+            }
+            state.dropped += dropped;
+        }
+        if is_frame {
+            state.frames += 1;
+        }
+        state.queue.push_back(Queued { message, is_frame });
+        drop(state);
+        self.shared.notify.notify_one();
+        Ok(dropped)
+    }
+
+    pub fn lag(&self) -> Lag {
+        let state = lock(&self.shared.state);
+        Lag {
+            queued: state.queue.len(),
+            dropped: state.dropped,
+        }
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        lock(&self.shared.state).sender_dropped = true;
+        self.shared.notify.notify_one();
+    }
+}
+
+/// Hands a peer's queued messages to its socket
+pub struct Receiver {
+    shared: Arc<Shared>,
+}
+
+impl Receiver {
+    /// Wait for the next message
+    /// # Returns
+    /// * `None` - Once the sender is dropped and the queue is empty
+    pub async fn recv(&mut self) -> Option<Message> {
+        loop {
+            {
+                let mut state = lock(&self.shared.state);
+                if let Some(message) = state.pop() {
+                    return Some(message);
+                }
+                if state.sender_dropped {
+                    return None;
+                }
+            }
+            self.shared.notify.notified().await;
+        }
+    }
+
+    /// The next message, if one is waiting
+    #[cfg(test)]
+    pub fn try_recv(&mut self) -> Option<Message> {
+        lock(&self.shared.state).pop()
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        lock(&self.shared.state).receiver_dropped = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn received(rx: &mut Receiver) -> Vec<Message> {
+        std::iter::from_fn(|| rx.try_recv()).collect()
+    }
+
+    #[test]
+    fn oldest_frame_is_dropped_without_a_resync() {
+        let (tx, mut rx) = channel(2);
+        tx.send_frame(Message::text("1"), || None).unwrap();
+        tx.send(Message::text("notice")).unwrap();
+        tx.send_frame(Message::text("2"), || None).unwrap();
+        assert_eq!(tx.send_frame(Message::text("3"), || None), Ok(1));
+        assert_eq!(
+            tx.lag(),
+            Lag {
+                queued: 3,
+                dropped: 1
+            }
+        );
+        // Control messages are kept
+        assert_eq!(
+            received(&mut rx),
+            vec![
+                Message::text("notice"),
+                Message::text("2"),
+                Message::text("3")
+            ]
+        );
+    }
+
+    #[test]
+    fn waiting_frames_are_replaced_by_the_resync() {
+        let (tx, mut rx) = channel(2);
+        tx.send_frame(Message::text("1"), || None).unwrap();
+        tx.send_frame(Message::text("2"), || None).unwrap();
+        tx.send(Message::text("notice")).unwrap();
+        let resync = || Some(Message::text("keyframe"));
+        assert_eq!(tx.send_frame(Message::text("3"), resync), Ok(2));
+        assert_eq!(
+            tx.lag(),
+            Lag {
+                queued: 2,
+                dropped: 2
+            }
+        );
+        assert_eq!(
+            received(&mut rx),
+            vec![Message::text("notice"), Message::text("keyframe")]
+        );
+
+        // The queue has room again
+        assert_eq!(tx.send_frame(Message::text("4"), resync), Ok(0));
+        assert_eq!(tx.lag().dropped, 2);
+    }
+
+    #[test]
+    fn peers_too_far_behind_on_messages_are_closed() {
+        let (tx, mut rx) = channel(2);
+        tx.send_frame(Message::text("frame"), || None).unwrap();
+        for _ in 0..MESSAGE_CAPACITY {
+            tx.send(Message::text("event")).unwrap();
+        }
+        assert_eq!(tx.send(Message::text("event")), Err(Closed));
+        assert_eq!(tx.send_frame(Message::text("frame"), || None), Err(Closed));
+        assert_eq!(tx.lag().dropped, 1);
+        let sent = received(&mut rx);
+        assert!(
+            matches!(&sent[..], [Message::Close(Some(close))] if close.code == CloseCode::Again)
+        );
+    }
+
+    #[test]
+    fn sending_to_a_closed_queue_fails() {
+        let (tx, rx) = channel(2);
+        drop(rx);
+        assert_eq!(tx.send(Message::text("notice")), Err(Closed));
+    }
+}
@@ -0,0 +1,238 @@
+use crate::lock::lock;
+use protocol::{DeltaDecoder, Frame, Record, WireMessage};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{error, info};
+
+enum Event {
+    Frame {
+        room: String,
+        received_at: u64,
+        frame: Box<Frame>,
+    },
+    Close {
+        room: String,
+    },
+    Shutdown,
+}
+
+/// Writes the frames producers send to one NDJSON file per match, named
+/// `{room}-{timestamp}.ndjson`. Files are written on a separate thread so
+/// that slow disks do not hold up the relay
+pub struct Recorder {
+    events: Sender<Event>,
+    writer: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Recorder {
+    /// Start recording into `dir`, creating it if needed
+    pub fn start(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let (events, receiver) = mpsc::channel();
+        let writer = thread::spawn(move || Writer::new(dir).run(receiver));
+        Ok(Recorder {
+            events,
+            writer: Mutex::new(Some(writer)),
+        })
+    }
+
+    /// Save a frame received in `room`, stamped with the current time
+    pub fn record(&self, room: &str, frame: &Frame) {
+        let _ = self.events.send(Event::Frame {
+            room: room.to_string(),
+            received_at: now(),
+            frame: Box::new(frame.clone()),
+        });
+    }
+
+    /// Close the recording of a room that is no longer in use
+    pub fn close(&self, room: &str) {
+        let _ = self.events.send(Event::Close {
+            room: room.to_string(),
+        });
+    }
+
+    /// Write out the frames still queued, close every recording and wait for
+    /// the files to be flushed
+    pub fn shutdown(&self) {
+        let _ = self.events.send(Event::Shutdown);
+        if let Some(writer) = lock(&self.writer).take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Milliseconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// An open recording file
+struct Recording {
+    path: PathBuf,
+    file: BufWriter<File>,
+    next_seq: u64,
+}
+
+impl Recording {
+    fn write(
+        &mut self,
+        received_at: u64,
+        frame: Frame,
+        events: Vec<protocol::Event>,
+    ) -> io::Result<()> {
+        let record = Record {
+            seq: self.next_seq,
+            received_at,
+            frame,
+            events,
+        };
+        let line = record.to_json().map_err(io::Error::other)?;
+        writeln!(self.file, "{}", line)?;
+        self.next_seq += 1;
+        Ok(())
+    }
+}
+
+/// The recording state of one room
+#[derive(Default)]
+struct RoomRecording {
+    /// Rebuilds the game state to detect new matches and start each file
+    /// with a keyframe
+    decoder: DeltaDecoder,
+    recording: Option<Recording>,
+}
+
+struct Writer {
+    dir: PathBuf,
+    rooms: HashMap<String, RoomRecording>,
+}
+
+impl Writer {
+    fn new(dir: PathBuf) -> Self {
+        Writer {
+            dir,
+            rooms: HashMap::new(),
+        }
+    }
+
+    /// Handle events until shutdown, flushing whenever the queue runs dry
+    fn run(mut self, events: Receiver<Event>) {
+        while let Ok(event) = events.recv() {
+            let mut next = Some(event);
+            while let Some(event) = next {
+                match event {
+                    Event::Frame {
+                        room,
+                        received_at,
+                        frame,
+                    } => self.write(room, received_at, *frame),
+                    Event::Close { room } => {
+                        if let Some(mut room) = self.rooms.remove(&room) {
+                            close(&mut room.recording);
+                        }
+                    }
+                    Event::Shutdown => {
+                        self.close_all();
+                        return;
+                    }
+                }
+                next = events.try_recv().ok();
+            }
+            self.flush();
+        }
+        self.close_all();
+    }
+
+    fn write(&mut self, room: String, received_at: u64, frame: Frame) {
+        let state = self.rooms.entry(room.clone()).or_default();
+        let previous = state.decoder.state().map(|(_, data)| data.clone());
+        let mut events = Vec::new();
+        let frame = match state.decoder.apply(frame.clone()) {
+            Ok(data) => {
+                let new_match = previous
+                    .as_ref()
+                    .is_some_and(|previous| data.game_info.starts_new_match(&previous.game_info));
+                if let Some(previous) = &previous {
+                    events = protocol::Event::between(previous, data);
+                }
+                if new_match || state.recording.is_none() {
+                    close(&mut state.recording);
+                    let path = self.dir.join(format!("{}-{}.ndjson", room, received_at));
+                    match File::create(&path) {
+                        Ok(file) => {
+                            info!(%room, path = %path.display(), "Recording room");
+                            state.recording = Some(Recording {
+                                path,
+                                file: BufWriter::new(file),
+                                next_seq: 0,
+                            });
+                        }
+                        Err(err) => {
+                            error!(path = %path.display(), %err, "Failed to create recording");
+                            return;
+                        }
+                    }
+                    // Every recording starts with the full state
+                    Frame::Keyframe {
+                        seq: frame.seq(),
+                        data: data.clone(),
+                    }
+                } else {
+                    frame
+                }
+            }
+            // Keep the frame so that replays see the same gap as live viewers
+            Err(_) => frame,
+        };
+        if let Some(recording) = &mut state.recording {
+            if let Err(err) = recording.write(received_at, frame, events) {
+                error!(path = %recording.path.display(), %err, "Failed to write recording");
+                state.recording = None;
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        for room in self.rooms.values_mut() {
+            if let Some(recording) = &mut room.recording {
+                if let Err(err) = recording.file.flush() {
+                    error!(path = %recording.path.display(), %err, "Failed to write recording");
+                    room.recording = None;
+                }
+            }
+        }
+    }
+
+    fn close_all(&mut self) {
+        for room in self.rooms.values_mut() {
+            close(&mut room.recording);
+        }
+    }
+}
+
+/// Flush and close a recording, logging where it was saved
+fn close(recording: &mut Option<Recording>) {
+    if let Some(mut recording) = recording.take() {
+        match recording.file.flush() {
+            Ok(()) => info!(
+                frames = recording.next_seq,
+                path = %recording.path.display(),
+                "Saved recording"
+            ),
+            Err(err) => error!(path = %recording.path.display(), %err, "Failed to write recording"),
+        }
+    }
+}
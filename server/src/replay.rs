@@ -0,0 +1,367 @@
+use crate::lock::lock;
+use crate::queue::{self, QUEUE_CAPACITY};
+use crate::room::{Peer, Rooms};
+use crate::wire;
+use protocol::{Control, DeltaDecoder, Encoding, Frame, Record, Role, WireMessage};
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    path::Path,
+    time::Duration,
+};
+use tokio::time::{sleep_until, Instant};
+use tracing::{debug, info, warn};
+
+/// Address the replay joins its room from, as if it were a producer
+const REPLAY_ADDR: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
+
+/// A recorded match served to viewers as if it were live. Viewers control it
+/// by sending [`Control::Pause`], [`Control::Resume`] and [`Control::Seek`]
+pub struct Replay {
+    records: Vec<Record>,
+    /// Playback speed, `2.0` plays twice as fast as the match was recorded
+    speed: f64,
+    /// Whether to start over once the recording ends
+    looping: bool,
+}
+
+impl Replay {
+    /// Read a recording written by the [`Recorder`](crate::recorder::Recorder)
+    /// # Arguments
+    /// * `path` - The NDJSON recording
+    /// * `speed` - The playback speed multiplier
+    /// * `looping` - Whether to start over at the end
+    /// # Returns
+    /// * `Err(io::Error)` - If the file cannot be read, has an invalid line or
+    ///   holds no frames
+    pub fn load(path: &Path, speed: f64, looping: bool) -> io::Result<Self> {
+        let records = fs::read_to_string(path)?
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                Record::from_json(line).map_err(|err| {
+                    io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("line {}: {}", index + 1, err),
+                    )
+                })
+            })
+            .collect::<io::Result<Vec<Record>>>()?;
+        if records.is_empty() {
+            return Err(io::Error::new(ErrorKind::InvalidData, "no frames recorded"));
+        }
+        Ok(Replay {
+            records,
+            speed,
+            looping,
+        })
+    }
+
+    /// The length of the recording in milliseconds
+    pub fn duration(&self) -> u64 {
+        self.offset(self.records.len() - 1)
+    }
+
+    /// Milliseconds between the start of the recording and a record
+    fn offset(&self, index: usize) -> u64 {
+        self.records[index]
+            .received_at
+            .saturating_sub(self.records[0].received_at)
+    }
+
+    /// Join `room` as its producer and play the recording until the server
+    /// shuts down
+    pub async fn run(self, rooms: Rooms, room: String) {
+        let (tx, mut controls) = queue::channel(QUEUE_CAPACITY);
+        lock(&rooms).entry(room.clone()).or_default().join(
+            REPLAY_ADDR,
+            Peer {
+                tx,
+                role: Role::Producer,
+                encoding: Encoding::Json,
+                team: None,
+                throttle: None,
+            },
+        );
+        info!(
+            %room,
+            frames = self.records.len(),
+            duration = self.duration() / 1000,
+            speed = self.speed,
+            "Replaying"
+        );
+
+        let mut playback = Playback::new(&self);
+        loop {
+            let due = playback.due();
+            tokio::select! {
+                _ = sleep_until(due.unwrap_or_else(Instant::now)), if due.is_some() => {
+                    if let Some(frame) = playback.step() {
+                        publish(&rooms, &room, frame);
+                    }
+                    if playback.is_finished() {
+                        if self.looping {
+                            info!(%room, "Replay finished, starting over");
+                            if let Some(frame) = playback.seek(0) {
+                                publish(&rooms, &room, frame);
+                            }
+                        } else {
+                            info!(%room, "Replay finished");
+                        }
+                    }
+                }
+                message = controls.recv() => {
+                    let Some(message) = message else {
+                        break;
+                    };
+                    let Some(control) = wire::decode::<Control>(&message) else {
+                        continue;
+                    };
+                    debug!(%room, ?control, "Replay received control");
+                    let frame = match control {
+                        Control::RequestKeyframe => playback.keyframe(),
+                        Control::Pause => {
+                            playback.pause();
+                            None
+                        }
+                        Control::Resume => {
+                            playback.resume();
+                            None
+                        }
+                        Control::Seek { position } => playback.seek(position),
+                        Control::SetFrameRate { .. } => None,
+                    };
+                    if let Some(frame) = frame {
+                        publish(&rooms, &room, frame);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn publish(rooms: &Rooms, room: &str, frame: Frame) {
+    if let Some(peers) = lock(rooms).get_mut(room) {
+        // A frame the room cannot apply is logged there, and the keyframe it
+        // asks for is sent by the playback
+        let _ = peers.publish(REPLAY_ADDR, &frame);
+    }
+}
+
+/// Position and clock of a replay. Frames are renumbered so that viewers see
+/// one continuous stream across seeks and loops
+struct Playback<'a> {
+    replay: &'a Replay,
+    /// Rebuilds the recorded state so that keyframes can be sent at any point
+    decoder: DeltaDecoder,
+    /// Index of the next record to play
+    next: usize,
+    /// Sequence number of the next frame sent
+    seq: u64,
+    /// Whether the next frame must be a keyframe, after a seek or a gap
+    needs_keyframe: bool,
+    paused: bool,
+    /// Recording position, in milliseconds, at `started`
+    position: u64,
+    started: Instant,
+}
+
+impl<'a> Playback<'a> {
+    fn new(replay: &'a Replay) -> Self {
+        Playback {
+            replay,
+            decoder: DeltaDecoder::new(),
+            next: 0,
+            seq: 0,
+            needs_keyframe: true,
+            paused: false,
+            position: 0,
+            started: Instant::now(),
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.next >= self.replay.records.len()
+    }
+
+    /// When the next record is due
+    /// # Returns
+    /// * `None` - If paused or the recording is over
+    fn due(&self) -> Option<Instant> {
+        if self.paused || self.is_finished() {
+            return None;
+        }
+        let ahead = self.replay.offset(self.next).saturating_sub(self.position);
+        Some(self.started + Duration::from_secs_f64(ahead as f64 / 1000.0 / self.replay.speed))
+    }
+
+    /// The recording position now, in milliseconds
+    fn current_position(&self) -> u64 {
+        if self.paused {
+            return self.position;
+        }
+        let elapsed = self.started.elapsed().as_secs_f64() * 1000.0 * self.replay.speed;
+        self.position + elapsed as u64
+    }
+
+    /// Play the next record
+    /// # Returns
+    /// * `None` - If the record does not follow the previous one, the replay
+    ///   then skips ahead to the next recorded keyframe
+    fn step(&mut self) -> Option<Frame> {
+        let record = &self.replay.records[self.next];
+        self.next += 1;
+        let frame = match (&record.frame, self.decoder.apply(record.frame.clone())) {
+            (_, Err(err)) => {
+                warn!(seq = record.seq, %err, "Replay skipped record");
+                self.needs_keyframe = true;
+                return None;
+            }
+            (Frame::Delta { patch, .. }, Ok(_)) if !self.needs_keyframe => Frame::Delta {
+                seq: self.seq,
+                base: None,
+                patch: patch.clone(),
+            },
+            (_, Ok(data)) => Frame::Keyframe {
+                seq: self.seq,
+                data: data.clone(),
+            },
+        };
+        self.needs_keyframe = false;
+        self.seq += 1;
+        Some(frame)
+    }
+
+    /// The current state as a keyframe, with the next sequence number
+    fn keyframe(&mut self) -> Option<Frame> {
+        let (_, data) = self.decoder.state()?;
+        let frame = Frame::Keyframe {
+            seq: self.seq,
+            data: data.clone(),
+        };
+        self.needs_keyframe = false;
+        self.seq += 1;
+        Some(frame)
+    }
+
+    fn pause(&mut self) {
+        self.position = self.current_position();
+        self.paused = true;
+    }
+
+    fn resume(&mut self) {
+        // Restarting the clock while playing would lose the time played
+        // since the last pause or seek
+        if !self.paused {
+            return;
+        }
+        self.started = Instant::now();
+        self.paused = false;
+    }
+
+    /// Jump to `position` milliseconds from the start of the recording
+    /// # Returns
+    /// * `Some(Frame)` - The state at that position as a keyframe
+    fn seek(&mut self, position: u64) -> Option<Frame> {
+        let records = &self.replay.records;
+        let target = (0..records.len())
+            .find(|&index| self.replay.offset(index) >= position)
+            .unwrap_or(records.len());
+        // Rebuild the state from the start, keyframes reset it along the way
+        self.decoder = DeltaDecoder::new();
+        for record in &records[..target] {
+            let _ = self.decoder.apply(record.frame.clone());
+        }
+        self.next = target;
+        self.position = position.min(self.replay.duration());
+        self.started = Instant::now();
+        self.needs_keyframe = true;
+        self.keyframe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::{Data, DataPatch};
+
+    /// A keyframe followed by a delta every second
+    fn replay() -> Replay {
+        let records = (0..4)
+            .map(|seq| Record {
+                seq,
+                received_at: 1_000_000 + seq * 1000,
+                frame: match seq {
+                    0 => Frame::Keyframe {
+                        seq,
+                        data: Data::builder().build(),
+                    },
+                    _ => Frame::Delta {
+                        seq,
+                        base: None,
+                        patch: DataPatch::default(),
+                    },
+                },
+                events: Vec::new(),
+            })
+            .collect();
+        Replay {
+            records,
+            speed: 1.0,
+            looping: false,
+        }
+    }
+
+    /// Pretend the playback has been running for `millis` since its clock
+    /// was last started
+    fn played_for(playback: &mut Playback, millis: u64) {
+        playback.started = Instant::now() - Duration::from_millis(millis);
+    }
+
+    #[test]
+    fn pause_holds_the_position() {
+        let replay = replay();
+        let mut playback = Playback::new(&replay);
+        played_for(&mut playback, 1500);
+        playback.pause();
+        let paused_at = playback.current_position();
+        assert!((1500..1600).contains(&paused_at));
+        assert_eq!(playback.due(), None);
+
+        playback.resume();
+        played_for(&mut playback, 500);
+        assert!((2000..2100).contains(&playback.current_position()));
+    }
+
+    #[test]
+    fn resume_while_playing_keeps_the_clock() {
+        let replay = replay();
+        let mut playback = Playback::new(&replay);
+        played_for(&mut playback, 1500);
+        playback.resume();
+        assert!(playback.current_position() >= 1500);
+    }
+
+    #[test]
+    fn seek_moves_the_position_and_sends_a_keyframe() {
+        let replay = replay();
+        let mut playback = Playback::new(&replay);
+        let keyframe = playback.seek(2000).unwrap();
+        assert!(keyframe.is_keyframe());
+        assert_eq!(playback.next, 2);
+        assert!((2000..2100).contains(&playback.current_position()));
+
+        // Seeking while paused stays paused at the new position
+        playback.pause();
+        playback.seek(1000);
+        assert_eq!(playback.current_position(), 1000);
+        assert_eq!(playback.due(), None);
+
+        // Past the end of the recording
+        playback.seek(10_000);
+        assert_eq!(playback.current_position(), replay.duration());
+        assert!(playback.is_finished());
+    }
+}
@@ -0,0 +1,742 @@
+use crate::lock::lock;
+use crate::metrics::Traffic;
+use crate::queue::{Lag, Sender};
+use crate::throttle::Throttle;
+use crate::wire;
+use protocol::{
+    Control, DeltaDecoder, DeltaError, Encoding, Event, Events, Frame, FrameError, Notice, Role,
+    Team, TeamView, WireMessage,
+};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tracing::{debug, warn};
+
+pub type Tx = Sender;
+pub type Rooms = Arc<Mutex<HashMap<String, Room>>>;
+
+/// Tell the viewers of every room whose producers went quiet for `timeout`
+pub async fn watch_stalls(rooms: Rooms, timeout: Duration) {
+    let mut ticks = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        ticks.tick().await;
+        for (id, room) in lock(&rooms).iter_mut() {
+            if room.check_stall(timeout) {
+                warn!(room = %id, timeout = timeout.as_secs(), "Room stalled");
+            }
+        }
+    }
+}
+
+/// A connected peer, its role and the encoding it accepted
+pub struct Peer {
+    pub tx: Tx,
+    pub role: Role,
+    pub encoding: Encoding,
+    /// Set for viewers only sent what one team sees
+    pub team: Option<Team>,
+    /// Set for viewers that asked for a lower frame rate
+    pub throttle: Option<Throttle>,
+}
+
+impl Peer {
+    pub fn lag(&self) -> Lag {
+        self.tx.lag()
+    }
+}
+
+/// The peers of one match, selected by the request path
+#[derive(Default)]
+pub struct Room {
+    peers: HashMap<SocketAddr, Peer>,
+    /// Latest game state published in the room, sent to viewers as they join
+    snapshot: DeltaDecoder,
+    /// The game state as seen by each team that has viewers in the room
+    views: HashMap<Team, TeamView>,
+    last_frame: Option<Instant>,
+    /// Whether the viewers were told that the feed stalled
+    stalled: bool,
+    pub traffic: Traffic,
+}
+
+impl Room {
+    /// Add a peer to the room. Viewers are sent the latest game state as a
+    /// keyframe first, so they can render before the producer's next frame
+    pub fn join(&mut self, addr: SocketAddr, peer: Peer) {
+        if let Some(team) = peer.team {
+            let snapshot = &self.snapshot;
+            self.views.entry(team).or_insert_with(|| {
+                let mut view = TeamView::new(team);
+                if let Some((seq, data)) = snapshot.state() {
+                    view.update(seq, data, true);
+                }
+                view
+            });
+        }
+        if peer.role == Role::Viewer {
+            if let Some(message) = self
+                .keyframe(peer.team)
+                .and_then(|keyframe| wire::encode(&keyframe, peer.encoding))
+            {
+                self.traffic.sent(message.len());
+                // The queue is empty and open, nothing can be dropped
+                let _ = peer.tx.send_frame(message, || None);
+            }
+            if let Some(notice) = self.stall_notice() {
+                if let Some(message) = wire::encode(&notice, peer.encoding) {
+                    self.traffic.sent(message.len());
+                    let _ = peer.tx.send(message);
+                }
+            }
+        }
+        self.peers.insert(addr, peer);
+    }
+
+    pub fn leave(&mut self, addr: &SocketAddr) -> Option<Peer> {
+        let peer = self.peers.remove(addr)?;
+        if let Some(team) = peer.team {
+            if !self.peers.values().any(|other| other.team == Some(team)) {
+                self.views.remove(&team);
+            }
+        }
+        Some(peer)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    pub fn peers(&self) -> impl Iterator<Item = (&SocketAddr, &Peer)> {
+        self.peers.iter()
+    }
+
+    /// The number of peers in `role`
+    pub fn count(&self, role: Role) -> usize {
+        self.peers.values().filter(|peer| peer.role == role).count()
+    }
+
+    /// Peers that should receive a message from `addr`: producers broadcast
+    /// to viewers, viewers reply to producers
+    /// # Arguments
+    /// * `addr` - The address of the sender
+    /// * `role` - The role of the sender
+    pub fn recipients(
+        &self,
+        addr: SocketAddr,
+        role: Role,
+    ) -> impl Iterator<Item = (&SocketAddr, &Peer)> {
+        self.peers
+            .iter()
+            .filter(move |(peer_addr, peer)| **peer_addr != addr && peer.role != role)
+    }
+
+    /// How long ago the room received a frame
+    /// # Returns
+    /// * `None` - If it never received one
+    pub fn last_frame_age(&self) -> Option<Duration> {
+        self.last_frame.map(|last_frame| last_frame.elapsed())
+    }
+
+    /// Whether the viewers were told that the feed stalled
+    pub fn is_stalled(&self) -> bool {
+        self.stalled
+    }
+
+    /// Check the game state a producer's frame would lead to before it is
+    /// published, deltas that do not follow the latest state are refused
+    pub fn validate(&self, frame: &Frame) -> Result<(), FrameError> {
+        self.snapshot.validate(frame)
+    }
+
+    /// Send a message to one peer, e.g. a reply to a message it sent
+    pub fn reply<T: WireMessage>(&mut self, addr: &SocketAddr, message: &T) {
+        let Some(peer) = self.peers.get(addr) else {
+            return;
+        };
+        if let Some(outgoing) = wire::encode(message, peer.encoding) {
+            self.traffic.sent(outgoing.len());
+            let _ = peer.tx.send(outgoing);
+        }
+    }
+
+    /// Queue a message for one peer as is, e.g. a close frame
+    pub fn send_raw(&mut self, addr: &SocketAddr, message: Message) {
+        if let Some(peer) = self.peers.get(addr) {
+            self.traffic.sent(message.len());
+            let _ = peer.tx.send(message);
+        }
+    }
+
+    /// The latest game state as a keyframe
+    /// # Arguments
+    /// * `team` - The team whose view of the state to send, `None` for all
+    ///   of it
+    /// # Returns
+    /// * `None` - If no keyframe has been received since the room opened or
+    ///   the last missed frame
+    pub fn keyframe(&self, team: Option<Team>) -> Option<Frame> {
+        keyframe(&self.snapshot, &self.views, team)
+    }
+
+    /// Limit the frames sent to a viewer, the frame it is owed is sent
+    /// under the new rate
+    /// # Arguments
+    /// * `addr` - The address of the viewer
+    /// * `fps` - The most frames per second, `0` sends every frame
+    pub fn set_frame_rate(&mut self, addr: &SocketAddr, fps: u32) {
+        let Some(viewer) = self
+            .peers
+            .get_mut(addr)
+            .filter(|peer| peer.role == Role::Viewer)
+        else {
+            return;
+        };
+        let pending = viewer.throttle.take().and_then(Throttle::into_pending);
+        viewer.throttle = Throttle::new(fps);
+        debug!(viewer = %addr, fps, "Set frame rate");
+
+        let Some((frame, events)) = pending else {
+            return;
+        };
+        let now = Instant::now();
+        let release = match &mut viewer.throttle {
+            Some(throttle) => throttle.push(frame, events, now),
+            None => Some((frame, events)),
+        };
+        if let Some((frame, events)) = release {
+            let (snapshot, views) = (&self.snapshot, &self.views);
+            let resync = || wire::encode(&keyframe(snapshot, views, viewer.team)?, viewer.encoding);
+            release_to(&mut self.traffic, addr, viewer, &frame, &events, resync);
+        }
+    }
+
+    /// Send a throttled viewer the frame held back for it, once it is due
+    /// # Returns
+    /// * `Some(Instant)` - When to check the viewer again
+    /// * `None` - If the viewer is not throttled
+    pub fn pace(&mut self, addr: &SocketAddr, now: Instant) -> Option<Instant> {
+        let viewer = self.peers.get_mut(addr)?;
+        let throttle = viewer.throttle.as_mut()?;
+        let due = throttle.take_due(now);
+        let next_check = throttle.next_check(now);
+        if let Some((frame, events)) = due {
+            let (snapshot, views) = (&self.snapshot, &self.views);
+            let resync = || wire::encode(&keyframe(snapshot, views, viewer.team)?, viewer.encoding);
+            release_to(&mut self.traffic, addr, viewer, &frame, &events, resync);
+        }
+        Some(next_check)
+    }
+
+    /// Update the latest game state with a producer's frame and broadcast it
+    /// to the viewers, followed by the [`Events`] found in it. Viewers of a
+    /// team are sent the frames and events of its [`TeamView`]. Throttled
+    /// viewers are sent the events once they are sent the frame. Viewers that
+    /// fell behind are sent the latest state in place of their waiting
+    /// frames
+    /// # Arguments
+    /// * `addr` - The address of the producer
+    /// * `frame` - The frame it sent, checked with [`Room::validate`]
+    /// # Returns
+    /// * `Err(DeltaError)` - If the frame does not follow the latest state, it
+    ///   is not relayed and the producers are asked for a keyframe
+    pub fn publish(&mut self, addr: SocketAddr, frame: &Frame) -> Result<(), DeltaError> {
+        self.last_frame = Some(Instant::now());
+        if self.stalled {
+            self.stalled = false;
+            self.notify(&Notice::Resumed);
+        }
+        let previous = self.snapshot.state().map(|(_, data)| data.clone());
+        let data = match self.snapshot.apply(frame.clone()) {
+            Ok(data) => data,
+            Err(err) => {
+                warn!(%err, "Snapshot out of date");
+                self.request_keyframe();
+                return Err(err);
+            }
+        };
+        let seq = frame.seq();
+        let found = |events: Vec<Event>| (!events.is_empty()).then_some(Events { seq, events });
+        let events = previous.and_then(|previous| found(Event::between(&previous, data)));
+        if let Some(events) = &events {
+            debug!(seq, events = ?events.events, "Derived events");
+        }
+        let seen: HashMap<Team, (Frame, Option<Events>)> = self
+            .views
+            .iter_mut()
+            .map(|(team, view)| {
+                let (frame, events) = view.update(seq, data, frame.is_keyframe());
+                (*team, (frame, found(events)))
+            })
+            .collect();
+
+        let now = Instant::now();
+        let (snapshot, views) = (&self.snapshot, &self.views);
+        let mut encoded: HashMap<(Option<Team>, Encoding), Option<Message>> = HashMap::new();
+        let mut encoded_events: HashMap<(Option<Team>, Encoding), Option<Message>> = HashMap::new();
+        let mut keyframes: HashMap<(Option<Team>, Encoding), Option<Message>> = HashMap::new();
+        let viewers = self
+            .peers
+            .iter_mut()
+            .filter(|(viewer_addr, viewer)| **viewer_addr != addr && viewer.role == Role::Viewer);
+        for (viewer_addr, viewer) in viewers {
+            let (frame, events) = match viewer.team {
+                None => (frame, &events),
+                Some(team) => match seen.get(&team) {
+                    Some((frame, events)) => (frame, events),
+                    None => continue,
+                },
+            };
+            let key = (viewer.team, viewer.encoding);
+            let resync = || {
+                keyframes
+                    .entry(key)
+                    .or_insert_with(|| {
+                        wire::encode(&keyframe(snapshot, views, viewer.team)?, viewer.encoding)
+                    })
+                    .clone()
+            };
+            // Throttled viewers are sent the frames held back so far merged
+            // into one, once they are due, followed by their events
+            if let Some(throttle) = &mut viewer.throttle {
+                let events = events.iter().cloned().collect();
+                if let Some((frame, events)) = throttle.push(frame.clone(), events, now) {
+                    release_to(
+                        &mut self.traffic,
+                        viewer_addr,
+                        viewer,
+                        &frame,
+                        &events,
+                        resync,
+                    );
+                }
+                continue;
+            }
+            let outgoing = encoded
+                .entry(key)
+                .or_insert_with(|| wire::encode(frame, viewer.encoding))
+                .clone();
+            let Some(outgoing) = outgoing else {
+                continue;
+            };
+            deliver(&mut self.traffic, viewer_addr, viewer, outgoing, resync);
+            // Events are sent after the frame they were found in and never
+            // dropped
+            let Some(events) = events else {
+                continue;
+            };
+            let outgoing = encoded_events
+                .entry(key)
+                .or_insert_with(|| wire::encode(events, viewer.encoding));
+            if let Some(outgoing) = outgoing {
+                self.traffic.sent(outgoing.len());
+                let _ = viewer.tx.send(outgoing.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Ask the producers for a keyframe, once per missed frame
+    fn request_keyframe(&mut self) {
+        if !self.snapshot.take_keyframe_request() {
+            return;
+        }
+        for producer in self
+            .peers
+            .values()
+            .filter(|peer| peer.role == Role::Producer)
+        {
+            if let Some(request) = wire::encode(&Control::RequestKeyframe, producer.encoding) {
+                self.traffic.sent(request.len());
+                let _ = producer.tx.send(request);
+            }
+        }
+    }
+
+    /// The stall notice for viewers, while the feed is stalled
+    fn stall_notice(&self) -> Option<Notice> {
+        match self.last_frame {
+            Some(last_frame) if self.stalled => Some(Notice::Stalled {
+                silent_for: last_frame.elapsed().as_millis() as u64,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Tell the viewers that the feed stalled if no frame arrived for
+    /// `timeout`. Rooms that never received a frame do not stall
+    /// # Returns
+    /// * `true` - If the feed just stalled
+    pub fn check_stall(&mut self, timeout: Duration) -> bool {
+        let silent = self
+            .last_frame
+            .is_some_and(|last_frame| last_frame.elapsed() >= timeout);
+        if self.stalled || !silent {
+            return false;
+        }
+        self.stalled = true;
+        if let Some(notice) = self.stall_notice() {
+            self.notify(&notice);
+        }
+        true
+    }
+
+    /// Send a message that must not be dropped to every viewer, e.g. a notice
+    fn notify<T: WireMessage>(&mut self, message: &T) {
+        self.notify_where(message, |_| true);
+    }
+
+    /// Send a message that must not be dropped to the viewers matching
+    /// `filter`
+    fn notify_where<T: WireMessage>(&mut self, message: &T, filter: impl Fn(&Peer) -> bool) {
+        let mut encoded: HashMap<Encoding, Option<Message>> = HashMap::new();
+        let viewers = self
+            .peers
+            .values()
+            .filter(|peer| peer.role == Role::Viewer && filter(peer));
+        for viewer in viewers {
+            let outgoing = encoded
+                .entry(viewer.encoding)
+                .or_insert_with(|| wire::encode(message, viewer.encoding));
+            if let Some(outgoing) = outgoing {
+                self.traffic.sent(outgoing.len());
+                let _ = viewer.tx.send(outgoing.clone());
+            }
+        }
+    }
+
+    /// Send a message to the recipients of `addr`, encoding it once per
+    /// encoding the recipients accepted. The recipients share the encoded
+    /// payload
+    pub fn broadcast<T: WireMessage>(&mut self, addr: SocketAddr, role: Role, message: &T) {
+        let mut encoded: HashMap<Encoding, Option<Message>> = HashMap::new();
+        let mut sent = Vec::new();
+        for (_, recp) in self.recipients(addr, role) {
+            let outgoing = encoded
+                .entry(recp.encoding)
+                .or_insert_with(|| wire::encode(message, recp.encoding))
+                .clone();
+            if let Some(outgoing) = outgoing {
+                sent.push(outgoing.len());
+                // Peers that disconnected are removed by their connection task
+                let _ = recp.tx.send(outgoing);
+            }
+        }
+        for len in sent {
+            self.traffic.sent(len);
+        }
+    }
+}
+
+/// The state of `snapshot` as a keyframe, or what `team` sees of it
+fn keyframe(
+    snapshot: &DeltaDecoder,
+    views: &HashMap<Team, TeamView>,
+    team: Option<Team>,
+) -> Option<Frame> {
+    let (seq, data) = snapshot.state()?;
+    match team {
+        None => Some(Frame::Keyframe {
+            seq,
+            data: data.clone(),
+        }),
+        Some(team) => views.get(&team)?.keyframe(),
+    }
+}
+
+/// Queue a frame let through by a viewer's throttle, then the events found
+/// in the frames merged into it
+/// # Arguments
+/// * `resync` - The latest state, sent in place of the waiting frames if the
+///   viewer fell behind
+fn release_to(
+    traffic: &mut Traffic,
+    addr: &SocketAddr,
+    viewer: &Peer,
+    frame: &Frame,
+    events: &[Events],
+    resync: impl FnOnce() -> Option<Message>,
+) {
+    let Some(message) = wire::encode(frame, viewer.encoding) else {
+        return;
+    };
+    deliver(traffic, addr, viewer, message, resync);
+    for events in events {
+        if let Some(message) = wire::encode(events, viewer.encoding) {
+            traffic.sent(message.len());
+            let _ = viewer.tx.send(message);
+        }
+    }
+}
+
+/// Queue a game-state frame for a viewer and count it
+/// # Arguments
+/// * `resync` - The latest state, sent in place of the waiting frames if the
+///   viewer fell behind
+fn deliver(
+    traffic: &mut Traffic,
+    addr: &SocketAddr,
+    viewer: &Peer,
+    message: Message,
+    resync: impl FnOnce() -> Option<Message>,
+) {
+    let len = message.len();
+    // Viewers that disconnected are removed by their connection task
+    if let Ok(dropped) = viewer.tx.send_frame(message, resync) {
+        traffic.sent(len);
+        if dropped > 0 {
+            traffic.dropped(dropped);
+            warn!(viewer = %addr, dropped, "Viewer fell behind");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::{self, Receiver};
+    use protocol::{Data, DataPatch, Player};
+
+    const PRODUCER: &str = "127.0.0.1:1";
+
+    fn peer(role: Role) -> (Peer, Receiver) {
+        let (tx, rx) = queue::channel(queue::QUEUE_CAPACITY);
+        let peer = Peer {
+            tx,
+            role,
+            encoding: Encoding::Json,
+            team: None,
+            throttle: None,
+        };
+        (peer, rx)
+    }
+
+    /// A viewer of `team` that may fall `capacity` frames behind
+    fn team_viewer(team: Team, capacity: usize) -> (Peer, Receiver) {
+        let (tx, rx) = queue::channel(capacity);
+        let peer = Peer {
+            tx,
+            role: Role::Viewer,
+            encoding: Encoding::Json,
+            team: Some(team),
+            throttle: None,
+        };
+        (peer, rx)
+    }
+
+    /// An attacker in plain sight and a defender the attackers cannot see
+    fn state() -> Data {
+        Data::builder()
+            .player(Player::builder("a1").position(100.0, 100.0).build())
+            .player(
+                Player::builder("d1")
+                    .team(Team::Defenders)
+                    .dormant(true)
+                    .position(500.0, 500.0)
+                    .credits(4000)
+                    .weapon(9)
+                    .build(),
+            )
+            .build()
+    }
+
+    /// Rebuild the state a viewer was sent, checking every frame on the way
+    fn replay(decoder: &mut DeltaDecoder, messages: &[Message], check: impl Fn(&Data)) {
+        for frame in messages.iter().filter_map(wire::decode::<Frame>) {
+            check(decoder.apply(frame).unwrap());
+        }
+    }
+
+    /// The attackers never saw the defender, so they are not sent it
+    fn hidden_from_attackers(data: &Data) {
+        assert_eq!(data.players.id, vec!["a1"]);
+    }
+
+    fn received(rx: &mut Receiver) -> Vec<Message> {
+        std::iter::from_fn(|| rx.try_recv()).collect()
+    }
+
+    fn delta(seq: u64) -> Frame {
+        Frame::Delta {
+            seq,
+            base: None,
+            patch: DataPatch::default(),
+        }
+    }
+
+    #[test]
+    fn frames_that_do_not_follow_the_snapshot_are_not_relayed() {
+        let producer: SocketAddr = PRODUCER.parse().unwrap();
+        let mut room = Room::default();
+        let (peer, mut producer_rx) = self::peer(Role::Producer);
+        room.join(producer, peer);
+        let (viewer, mut viewer_rx) = self::peer(Role::Viewer);
+        room.join("127.0.0.1:2".parse().unwrap(), viewer);
+
+        // No keyframe yet
+        assert!(room.validate(&delta(1)).is_err());
+        assert!(room.publish(producer, &delta(1)).is_err());
+        assert!(received(&mut viewer_rx).is_empty());
+        let request = wire::encode(&Control::RequestKeyframe, Encoding::Json).unwrap();
+        assert_eq!(received(&mut producer_rx), vec![request]);
+
+        let keyframe = Frame::Keyframe {
+            seq: 1,
+            data: Data::builder().build(),
+        };
+        room.publish(producer, &keyframe).unwrap();
+        room.publish(producer, &delta(2)).unwrap();
+        assert_eq!(received(&mut viewer_rx).len(), 2);
+
+        // A gap after seq 2
+        assert!(room.validate(&delta(4)).is_err());
+        assert!(room.publish(producer, &delta(4)).is_err());
+        assert!(received(&mut viewer_rx).is_empty());
+        assert_eq!(received(&mut producer_rx).len(), 1);
+    }
+
+    #[test]
+    fn team_viewers_are_never_sent_what_their_team_cannot_see() {
+        let producer: SocketAddr = PRODUCER.parse().unwrap();
+        let mut room = Room::default();
+        room.publish(
+            producer,
+            &Frame::Keyframe {
+                seq: 1,
+                data: state(),
+            },
+        )
+        .unwrap();
+
+        let (viewer, mut team_rx) = team_viewer(Team::Attackers, queue::QUEUE_CAPACITY);
+        room.join("127.0.0.1:2".parse().unwrap(), viewer);
+        let (observer, mut observer_rx) = peer(Role::Viewer);
+        room.join("127.0.0.1:3".parse().unwrap(), observer);
+
+        // The hidden defender moves and is killed
+        let mut next = state();
+        next.players.x[1] = 600.0;
+        next.players.health[1] = 0;
+        next.players.kill[0] = 1;
+        let delta = Frame::Delta {
+            seq: 2,
+            base: None,
+            patch: DataPatch::diff(&state(), &next),
+        };
+        room.publish(producer, &delta).unwrap();
+
+        // The keyframe sent at join and the delta after it, with no kill
+        let sent = received(&mut team_rx);
+        let mut team = DeltaDecoder::new();
+        replay(&mut team, &sent, hidden_from_attackers);
+        assert_eq!(team.state().unwrap().0, 2);
+        assert!(sent
+            .iter()
+            .all(|message| wire::decode::<Events>(message).is_none()));
+
+        // Observers see everything
+        let sent = received(&mut observer_rx);
+        let mut observer = DeltaDecoder::new();
+        replay(&mut observer, &sent, |_| {});
+        assert_eq!(observer.state().unwrap().1, &next);
+        let kill = sent
+            .iter()
+            .find_map(wire::decode::<Events>)
+            .expect("the kill is sent");
+        assert!(matches!(
+            &kill.events[..],
+            [Event::Kill { x, y, .. }] if (*x, *y) == (600.0, 500.0)
+        ));
+    }
+
+    #[test]
+    fn team_viewers_are_resynced_with_their_view() {
+        let producer: SocketAddr = PRODUCER.parse().unwrap();
+        let mut room = Room::default();
+        let (viewer, mut team_rx) = team_viewer(Team::Attackers, 2);
+        room.join("127.0.0.1:2".parse().unwrap(), viewer);
+
+        let mut data = state();
+        room.publish(
+            producer,
+            &Frame::Keyframe {
+                seq: 1,
+                data: data.clone(),
+            },
+        )
+        .unwrap();
+        // The viewer falls behind while the defender stays hidden
+        for seq in 2..6 {
+            let previous = data.clone();
+            data.players.x[1] += 10.0;
+            let delta = Frame::Delta {
+                seq,
+                base: None,
+                patch: DataPatch::diff(&previous, &data),
+            };
+            room.publish(producer, &delta).unwrap();
+        }
+        assert!(room.peers().all(|(_, viewer)| viewer.lag().dropped > 0));
+        let sent = received(&mut team_rx);
+        let mut team = DeltaDecoder::new();
+        replay(&mut team, &sent, hidden_from_attackers);
+        assert_eq!(team.state().unwrap().0, 5);
+
+        // The keyframe a team viewer asks for is its view too
+        hidden_from_attackers(&keyframe_data(room.keyframe(Some(Team::Attackers))));
+        assert_eq!(keyframe_data(room.keyframe(None)), data);
+    }
+
+    #[test]
+    fn throttled_viewers_get_events_after_their_frame() {
+        let producer: SocketAddr = PRODUCER.parse().unwrap();
+        let viewer_addr: SocketAddr = "127.0.0.1:2".parse().unwrap();
+        let mut room = Room::default();
+        let (viewer, mut viewer_rx) = peer(Role::Viewer);
+        room.join(viewer_addr, viewer);
+        room.set_frame_rate(&viewer_addr, 1);
+        room.publish(
+            producer,
+            &Frame::Keyframe {
+                seq: 1,
+                data: state(),
+            },
+        )
+        .unwrap();
+        assert_eq!(received(&mut viewer_rx).len(), 1);
+
+        // The kill is held back with the frame it was found in
+        let mut next = state();
+        next.players.health[1] = 0;
+        next.players.kill[0] = 1;
+        let delta = Frame::Delta {
+            seq: 2,
+            base: None,
+            patch: DataPatch::diff(&state(), &next),
+        };
+        room.publish(producer, &delta).unwrap();
+        assert!(received(&mut viewer_rx).is_empty());
+
+        let later = Instant::now() + Duration::from_secs(1);
+        assert!(room.pace(&viewer_addr, later).is_some());
+        let sent = received(&mut viewer_rx);
+        assert_eq!(sent.len(), 2);
+        assert_eq!(
+            wire::decode::<Frame>(&sent[0]).map(|frame| frame.seq()),
+            Some(2)
+        );
+        let events = wire::decode::<Events>(&sent[1]).expect("the kill follows its frame");
+        assert_eq!(events.seq, 2);
+        assert!(matches!(&events.events[..], [Event::Kill { .. }]));
+    }
+
+    fn keyframe_data(frame: Option<Frame>) -> Data {
+        match frame {
+            Some(Frame::Keyframe { data, .. }) => data,
+            frame => panic!("expected a keyframe, got {:?}", frame),
+        }
+    }
+}
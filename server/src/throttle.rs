@@ -0,0 +1,212 @@
+use protocol::{Events, Frame};
+use std::time::{Duration, Instant};
+
+/// A frame let through and the events found in the frames merged into it,
+/// to send after the frame
+pub type Release = (Frame, Vec<Events>);
+
+/// Holds back the frames of a viewer that asked for a lower frame rate. The
+/// frames received in between are merged, so the viewer always ends up with
+/// the newest state. Their events are held back with them, so that the
+/// viewer never gets events for a frame it was not sent yet
+#[derive(Debug)]
+pub struct Throttle {
+    interval: Duration,
+    next_due: Instant,
+    pending: Option<Frame>,
+    events: Vec<Events>,
+}
+
+impl Throttle {
+    /// Create a throttle
+    /// # Arguments
+    /// * `fps` - The most frames to let through per second
+    /// # Returns
+    /// * `None` - If `fps` is `0`, every frame is let through
+    pub fn new(fps: u32) -> Option<Self> {
+        (fps > 0).then(|| Throttle {
+            interval: Duration::from_secs(1) / fps,
+            next_due: Instant::now(),
+            pending: None,
+            events: Vec::new(),
+        })
+    }
+
+    /// Offer a frame to the viewer
+    /// # Arguments
+    /// * `frame` - The next frame for the viewer
+    /// * `events` - The events found in it
+    /// * `now` - When the frame was received
+    /// # Returns
+    /// * `Some(Release)` - The frame to send now, merged with the frames held
+    ///   back before it, and their events
+    /// * `None` - If the frame was held back until the next one is due
+    pub fn push(&mut self, frame: Frame, events: Vec<Events>, now: Instant) -> Option<Release> {
+        self.pending = Some(match self.pending.take() {
+            Some(pending) => pending.coalesce(frame),
+            None => frame,
+        });
+        self.events.extend(events);
+        self.take_due(now)
+    }
+
+    /// The frame held back and its events, once the next frame is due
+    pub fn take_due(&mut self, now: Instant) -> Option<Release> {
+        if now < self.next_due {
+            return None;
+        }
+        let frame = self.pending.take()?;
+        // Keep to the schedule when the sweep is late, restart it after the
+        // feed was idle so the next frame is not sent right behind this one
+        let next_due = self.next_due + self.interval;
+        self.next_due = if next_due > now {
+            next_due
+        } else {
+            now + self.interval
+        };
+        Some((frame, std::mem::take(&mut self.events)))
+    }
+
+    /// When a frame held back after `now` may be due, for the viewer's task
+    /// to send it. Frames offered after the next frame is due are let
+    /// through by [`Throttle::push`], so there is nothing to check before
+    /// then
+    pub fn next_check(&self, now: Instant) -> Instant {
+        if self.next_due > now {
+            self.next_due
+        } else {
+            now + self.interval
+        }
+    }
+
+    /// The frame held back and its events, to send when the viewer changes
+    /// its rate
+    pub fn into_pending(self) -> Option<Release> {
+        Some((self.pending?, self.events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::{DataPatch, Event, PlayersPatch};
+
+    /// Well after any throttle created by the test, so its schedule starts
+    /// with the first frame
+    fn later() -> Instant {
+        Instant::now() + Duration::from_secs(1)
+    }
+
+    fn patch(x: f64) -> DataPatch {
+        DataPatch {
+            players: PlayersPatch {
+                x: Some(vec![x]),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn events(seq: u64) -> Events {
+        Events {
+            seq,
+            events: vec![Event::SidesSwapped],
+        }
+    }
+
+    fn delta(seq: u64, x: f64) -> Frame {
+        Frame::Delta {
+            seq,
+            base: None,
+            patch: patch(x),
+        }
+    }
+
+    #[test]
+    fn frames_are_capped_to_the_rate() {
+        let mut throttle = Throttle::new(10).unwrap();
+        let start = later();
+        let interval = Duration::from_millis(100);
+        assert_eq!(
+            throttle.push(delta(1, 1.0), Vec::new(), start),
+            Some((delta(1, 1.0), Vec::new()))
+        );
+
+        // Frames within the interval are held back and merged
+        let held = throttle.push(delta(2, 2.0), Vec::new(), start + interval / 4);
+        assert_eq!(held, None);
+        let held = throttle.push(delta(3, 3.0), Vec::new(), start + interval / 2);
+        assert_eq!(held, None);
+        assert_eq!(throttle.take_due(start + interval / 2), None);
+        assert_eq!(throttle.next_check(start + interval / 2), start + interval);
+
+        let (merged, _) = throttle.take_due(start + interval).unwrap();
+        assert_eq!(
+            merged,
+            Frame::Delta {
+                seq: 3,
+                base: Some(1),
+                patch: patch(3.0),
+            }
+        );
+        // Nothing is left to flush
+        assert_eq!(throttle.take_due(start + interval * 3), None);
+    }
+
+    #[test]
+    fn frames_after_an_idle_feed_are_let_through() {
+        let mut throttle = Throttle::new(10).unwrap();
+        let start = later();
+        let interval = Duration::from_millis(100);
+        throttle.push(delta(1, 1.0), Vec::new(), start);
+
+        let resumed = start + interval * 5;
+        assert_eq!(throttle.next_check(resumed), resumed + interval);
+        assert_eq!(
+            throttle.push(delta(2, 2.0), Vec::new(), resumed),
+            Some((delta(2, 2.0), Vec::new()))
+        );
+        // The schedule restarts from the frame let through
+        let held = throttle.push(delta(3, 3.0), Vec::new(), resumed + interval / 2);
+        assert_eq!(held, None);
+        assert_eq!(throttle.next_check(resumed), resumed + interval);
+    }
+
+    #[test]
+    fn no_throttle_without_a_rate() {
+        assert!(Throttle::new(0).is_none());
+    }
+
+    #[test]
+    fn held_back_frame_is_kept_when_the_rate_changes() {
+        let mut throttle = Throttle::new(1).unwrap();
+        let start = later();
+        throttle.push(delta(1, 1.0), Vec::new(), start);
+        throttle.push(delta(2, 2.0), vec![events(2)], start);
+        assert_eq!(
+            throttle.into_pending(),
+            Some((delta(2, 2.0), vec![events(2)]))
+        );
+    }
+
+    #[test]
+    fn events_are_held_back_with_their_frame() {
+        let mut throttle = Throttle::new(10).unwrap();
+        let start = later();
+        let interval = Duration::from_millis(100);
+        throttle.push(delta(1, 1.0), Vec::new(), start);
+
+        let held = throttle.push(delta(2, 2.0), vec![events(2)], start + interval / 4);
+        assert_eq!(held, None);
+        let held = throttle.push(delta(3, 3.0), vec![events(3)], start + interval / 2);
+        assert_eq!(held, None);
+        let (merged, released) = throttle.take_due(start + interval).unwrap();
+        assert_eq!(merged.seq(), 3);
+        assert_eq!(released, vec![events(2), events(3)]);
+        // Events are only sent once
+        let (_, released) = throttle
+            .push(delta(4, 4.0), Vec::new(), start + interval * 2)
+            .unwrap();
+        assert!(released.is_empty());
+    }
+}
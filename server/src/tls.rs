@@ -0,0 +1,50 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+
+/// How long a client may take to complete the TLS handshake
+pub const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Build the acceptor for `wss://` and `https://` connections
+/// # Arguments
+/// * `cert` - PEM file holding the certificate chain, leaf first
+/// * `key` - PEM file holding the private key of the leaf certificate
+/// # Returns
+/// * `Err(io::Error)` - If a file cannot be read, holds no certificate or key,
+///   or the key does not match the certificate
+pub fn acceptor(cert: &Path, key: &Path) -> io::Result<TlsAcceptor> {
+    let invalid = |path: &Path, err: &dyn std::fmt::Display| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), err),
+        )
+    };
+
+    let read = |path: &Path| fs::read(path).map_err(|err| invalid(path, &err));
+    let chain = CertificateDer::pem_slice_iter(&read(cert)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| invalid(cert, &err))?;
+    if chain.is_empty() {
+        return Err(invalid(cert, &"no certificate found"));
+    }
+    let key_der = PrivateKeyDer::from_pem_slice(&read(key)?).map_err(|err| invalid(key, &err))?;
+
+    let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| {
+            builder
+                .with_no_client_auth()
+                .with_single_cert(chain, key_der)
+        })
+        .map_err(|err| invalid(key, &err))?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
@@ -0,0 +1,53 @@
+use protocol::{Encoding, WireMessage};
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tracing::error;
+
+/// Encode a message as a text or binary WebSocket message. The payload is
+/// reference counted, so clones sent to several peers share it
+/// # Arguments
+/// * `message` - The protocol message
+/// * `encoding` - The encoding negotiated by the recipient
+/// # Returns
+/// * `None` - If the message could not be serialised, which is logged
+pub fn encode<T: WireMessage>(message: &T, encoding: Encoding) -> Option<Message> {
+    let encoded = match encoding {
+        Encoding::Json => message
+            .to_json()
+            .map(Message::text)
+            .map_err(|err| err.to_string()),
+        Encoding::MessagePack => message
+            .to_msgpack()
+            .map(Message::binary)
+            .map_err(|err| err.to_string()),
+    };
+    encoded
+        .map_err(|err| error!(?encoding, %err, "Failed to encode a message"))
+        .ok()
+}
+
+/// Decode a WebSocket message, text is read as JSON and binary as MessagePack
+/// # Returns
+/// * `None` - If the message is not a `T`
+pub fn decode<T: WireMessage>(msg: &Message) -> Option<T> {
+    try_decode(msg).ok()
+}
+
+/// Decode a WebSocket message like [`decode`]
+/// # Returns
+/// * `Err(String)` - Why the message is not a `T`
+pub fn try_decode<T: WireMessage>(msg: &Message) -> Result<T, String> {
+    match msg {
+        Message::Text(text) => T::from_json(text).map_err(|err| err.to_string()),
+        Message::Binary(bytes) => T::from_msgpack(bytes).map_err(|err| err.to_string()),
+        _ => Err("not a data message".to_string()),
+    }
+}
+
+/// The encoding a message was sent in
+pub fn encoding_of(msg: &Message) -> Encoding {
+    if msg.is_binary() {
+        Encoding::MessagePack
+    } else {
+        Encoding::Json
+    }
+}
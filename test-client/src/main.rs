@@ -1,137 +1,227 @@
-use json::object;
+use protocol::{
+    room_path, Control, Data, DeltaEncoder, Encoding, Frame, GameInfo, Hello, HelloReply, Map,
+    Notice, Player, Role, RoundOutcome, Team, WireMessage, AGENT_COUNT, DEFAULT_ROOM,
+    SUBPROTOCOL_HEADER, WEAPON_COUNT,
+};
+use rand::rngs::ThreadRng;
 use rand::Rng;
-use std::{thread, time};
+use std::{env, thread, time};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::http::HeaderValue;
+use tungstenite::stream::Stream;
 use tungstenite::{connect, Message};
 
+/// Send a keyframe at least this often, in ticks
+const KEYFRAME_INTERVAL: u64 = 10;
+/// Start a new random round this often, in ticks
+const ROUND_INTERVAL: u64 = 15;
+/// Display names of the simulated players, the index doubles as their id
+const PLAYER_NAMES: [&str; 10] = [
+    "Ember", "Quill", "Nyx", "Basalt", "Sparrow", "Tundra", "Vesper", "Marrow", "Juno", "Cinder",
+];
+
+/// Generate a random game state
+fn random_state(rng: &mut ThreadRng) -> Data {
+    let played_rounds = rng.gen_range(0..30);
+    let mut max_rounds = 24;
+    let spike_planted = rng.gen_bool(0.5);
+    while played_rounds > max_rounds {
+        max_rounds += 2;
+    }
+    let round_win_status = (0..max_rounds)
+        .map(|i| {
+            if i < played_rounds {
+                RoundOutcome::won_by(Team::ALL[rng.gen_range(0..2)])
+            } else {
+                RoundOutcome::Unplayed
+            }
+        })
+        .collect();
+
+    let mut game_info = GameInfo::builder()
+        .map(Map::ALL[rng.gen_range(0..Map::ALL.len())])
+        .round_win_status(round_win_status)
+        .played_rounds(played_rounds)
+        .max_rounds(max_rounds);
+    if spike_planted {
+        let spike_time = rng.gen_range(0.0..45.0);
+        game_info = game_info
+            .spike(
+                rng.gen_range(0.0..1000.0),
+                rng.gen_range(0.0..1000.0),
+                spike_time,
+            )
+            .round_time(spike_time);
+    } else {
+        game_info = game_info.round_time(rng.gen_range(0.0..150.0));
+    }
+
+    let mut data = Data::builder().game_info(game_info.build());
+    let mut defusing_spike = false;
+    for (i, name) in PLAYER_NAMES.iter().enumerate() {
+        // Only one defender can be defusing a planted spike
+        let defusing = spike_planted && !defusing_spike && i > 4;
+        if defusing {
+            defusing_spike = true;
+        }
+        // Agents are drawn independently, so mirror picks happen
+        let mut player = Player::builder(&format!("player-{}", i))
+            .name(name)
+            .agent(rng.gen_range(0..AGENT_COUNT))
+            .position(rng.gen_range(0.0..1000.0), rng.gen_range(0.0..1000.0))
+            .health(rng.gen_range(0..101))
+            .team(if i < 5 {
+                Team::Attackers
+            } else {
+                Team::Defenders
+            })
+            .dormant(rng.gen_bool(0.5))
+            .rotation(rng.gen_range(0.0..360.0))
+            .scoped(rng.gen_bool(0.5))
+            .weapon(rng.gen_range(0..WEAPON_COUNT))
+            .score(
+                rng.gen_range(0..30),
+                rng.gen_range(0..30),
+                rng.gen_range(0..30),
+                rng.gen_range(0..400),
+            )
+            .shield(rng.gen_range(0..50))
+            .credits(rng.gen_range(0..16000));
+        if defusing {
+            player = player.defusing(rng.gen_range(0.0..8.0));
+        }
+        data = data.player(player.build());
+    }
+    data.build()
+}
+
+/// Move every player and count down the round timer, leaving the
+/// scoreboard and round history untouched
+fn tick_state(rng: &mut ThreadRng, data: &mut Data) {
+    let players = &mut data.players;
+    for i in 0..players.len() {
+        players.x[i] = (players.x[i] + rng.gen_range(-25.0..25.0)).clamp(0.0, 1000.0);
+        players.y[i] = (players.y[i] + rng.gen_range(-25.0..25.0)).clamp(0.0, 1000.0);
+        players.rotation[i] = rng.gen_range(0.0..360.0);
+    }
+    let round_time = &mut data.game_info.round_time[0];
+    *round_time = (*round_time - 1.0).max(0.0);
+}
+
+/// Encode a message in the negotiated encoding
+fn encode<T: WireMessage>(message: &T, encoding: Encoding) -> Result<Message, String> {
+    match encoding {
+        Encoding::Json => message
+            .to_json()
+            .map(Message::from)
+            .map_err(|e| e.to_string()),
+        Encoding::MessagePack => message
+            .to_msgpack()
+            .map(Message::from)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Decode a message, text as JSON and binary as MessagePack
+fn decode<T: WireMessage>(message: &Message) -> Option<T> {
+    match message {
+        Message::Text(text) => T::from_json(text).ok(),
+        Message::Binary(bytes) => T::from_msgpack(bytes).ok(),
+        _ => None,
+    }
+}
+
 fn main() {
+    // The match to produce for, e.g. `cargo run --bin test-client scrim-1`
+    let room = env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ROOM.to_string());
+    let url = format!("ws://localhost:27017{}", room_path(&room));
+    // Required when the server sets RADAR_PRODUCER_TOKENS
+    let token = env::var("RADAR_TOKEN").ok();
+    println!("Producing for room {}", room);
     loop {
-        match connect("ws://localhost:27017") {
-            Ok((mut socket, _response)) => loop {
-                let mut rng = rand::thread_rng();
-                let _played_rounds = rng.gen_range(0..30);
-                let mut _max_rounds = 24;
-                let mut _spike_planted = rng.gen_range(0..2);
-                let mut _spike_x = json::Array::new();
-                let mut _spike_y = json::Array::new();
-                let mut _spike_time = json::Array::new();
-                let mut _round_win_status = json::Array::new();
-                let mut _round_time = json::Array::new();
-                if _spike_planted == 1 {
-                    _spike_x.push(json::from(rng.gen_range(0.0..1000.0)));
-                    _spike_y.push(json::from(rng.gen_range(0.0..1000.0)));
-                    _spike_time.push(json::from(rng.gen_range(0.0..45.0)));
-                    _round_time.push(_spike_time[0].clone());
-                } else {
-                    _spike_x.push(json::from(0));
-                    _spike_y.push(json::from(0));
-                    _spike_time.push(json::from(0));
-                    _round_time.push(json::from(rng.gen_range(0.0..150.0)));
-                }
-                while _played_rounds > _max_rounds {
-                    _max_rounds += 2;
-                }
-                for _i in 0.._max_rounds {
-                    if _i < _played_rounds {
-                        _round_win_status.push(json::from(rng.gen_range(0..2)));
-                    } else {
-                        _round_win_status.push(json::from(2));
-                    }
+        let mut request = url.as_str().into_client_request().unwrap();
+        let offered = Encoding::PREFERRED.map(|encoding| encoding.subprotocol());
+        request.headers_mut().insert(
+            SUBPROTOCOL_HEADER,
+            HeaderValue::from_str(&offered.join(", ")).unwrap(),
+        );
+        match connect(request) {
+            Ok((mut socket, response)) => {
+                // Servers that do not negotiate a subprotocol only understand JSON
+                let negotiated = response
+                    .headers()
+                    .get(SUBPROTOCOL_HEADER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(Encoding::from_subprotocol)
+                    .unwrap_or(Encoding::Json);
+                let hello = match &token {
+                    Some(token) => Hello::new(Role::Producer).with_token(token),
+                    None => Hello::new(Role::Producer),
+                };
+                let hello = encode(&hello, negotiated).unwrap();
+                if let Err(e) = socket.write_message(hello) {
+                    eprintln!("Error sending hello: {}", e);
+                    thread::sleep(time::Duration::from_millis(5000));
+                    continue;
                 }
-                let mut _id = json::Array::new();
-                let mut _x = json::Array::new();
-                let mut _y = json::Array::new();
-                let mut _health = json::Array::new();
-                let mut _team = json::Array::new();
-                let mut _dormant = json::Array::new();
-                let mut _rotation = json::Array::new();
-                let mut _scoped = json::Array::new();
-                let mut _weapon = json::Array::new();
-                let mut _kill = json::Array::new();
-                let mut _death = json::Array::new();
-                let mut _assist = json::Array::new();
-                let mut _acs = json::Array::new();
-                let mut _shield = json::Array::new();
-                let mut _credits = json::Array::new();
-                let mut _defuse_time = json::Array::new();
-                let mut _defusing = json::Array::new();
-                let mut _defusing_spike = 0;
-
-                for _i in 0..10 {
-                    _id.push(json::from(rng.gen_range(0..22)));
-                    _x.push(json::from(rng.gen_range(0.0..1000.0)));
-                    _y.push(json::from(rng.gen_range(0.0..1000.0)));
-                    _health.push(json::from(rng.gen_range(0..101)));
-                    match _i {
-                        0..=4 => _team.push(json::from(0)),
-                        5..=10 => _team.push(json::from(1)),
-                        _ => (),
+                let encoding = match socket.read_message().map(|reply| decode(&reply)) {
+                    Ok(Some(HelloReply::Welcome { encoding, .. })) => encoding,
+                    Ok(Some(HelloReply::Rejected { error })) => {
+                        eprintln!("Server refused the connection: {}", error);
+                        thread::sleep(time::Duration::from_millis(5000));
+                        continue;
                     }
-                    _dormant.push(json::from(rng.gen_range(0..2)));
-                    _rotation.push(json::from(rng.gen_range(0.0..360.0)));
-                    _scoped.push(json::from(rng.gen_range(0..2)));
-                    _weapon.push(json::from(rng.gen_range(0..100)));
-                    _kill.push(json::from(rng.gen_range(0..30)));
-                    _death.push(json::from(rng.gen_range(0..30)));
-                    _assist.push(json::from(rng.gen_range(0..30)));
-                    _acs.push(json::from(rng.gen_range(0..400)));
-                    _shield.push(json::from(rng.gen_range(0..50)));
-                    _credits.push(json::from(rng.gen_range(0..16000)));
-                    _defusing.push(json::from(0));
-                    if _spike_planted == 1 && _defusing_spike == 0 && _i > 4 {
-                        _defuse_time.push(json::from(rng.gen_range(0.0..8.0)));
-                        _defusing.pop();
-                        _defusing.push(json::from(1));
-                        _defusing_spike = 1;
-                    } else {
-                        _defuse_time.push(json::from(-1.0));
+                    _ => {
+                        eprintln!("Server did not reply to the hello");
+                        thread::sleep(time::Duration::from_millis(5000));
+                        continue;
                     }
-                }
-                let players = object! {
-                "id": _id,
-                "x": _x,
-                "y": _y,
-                "health": _health,
-                "team": _team,
-                "dormant": _dormant,
-                "rotation": _rotation,
-                "scoped": _scoped,
-                "weapon": _weapon,
-                "kill": _kill,
-                "death": _death,
-                "assist": _assist,
-                "acs": _acs,
-                "shield": _shield,
-                "credits": _credits,
-                "defusing": _defusing,
-                "defuse_time": _defuse_time,
-                };
-
-                let game_info = object! {
-                "spike_planted": _spike_planted,
-                "spike_x": _spike_x,
-                "spike_y": _spike_y,
-                "spike_time": _spike_time,
-                "round_win_status": _round_win_status,
-                "played_rounds": _played_rounds,
-                "max_rounds": _max_rounds,
-                "round_time" : _round_time,
-                };
-
-                let data = object! {
-                "players": players,
-                "game_info": game_info,
                 };
+                // Poll for control messages without blocking the send loop
+                if let Stream::Plain(stream) = socket.get_mut() {
+                    stream
+                        .set_read_timeout(Some(time::Duration::from_millis(1)))
+                        .unwrap();
+                }
+                let mut rng = rand::thread_rng();
+                let mut encoder = DeltaEncoder::new(KEYFRAME_INTERVAL);
+                let mut state = random_state(&mut rng);
+                for tick in 1.. {
+                    while let Ok(message) = socket.read_message() {
+                        if let Some(Control::RequestKeyframe) = decode(&message) {
+                            println!("Keyframe requested");
+                            encoder.request_keyframe();
+                        } else if let Some(Notice::Rejected { seq, error }) = decode(&message) {
+                            eprintln!("Frame {:?} rejected: {}", seq, error);
+                        }
+                    }
 
-                match socket.write_message(Message::from(data.dump())) {
-                    Ok(_) => {
-                        thread::sleep(time::Duration::from_millis(1000));
+                    if tick % ROUND_INTERVAL == 0 {
+                        state = random_state(&mut rng);
+                    } else {
+                        tick_state(&mut rng, &mut state);
                     }
-                    Err(_) => {
-                        eprintln!("Error sending message. Attempting to reconnect...");
-                        break;
+                    let frame: Frame = encoder.encode(state.clone());
+                    let message = match encode(&frame, encoding) {
+                        Ok(message) => message,
+                        Err(e) => {
+                            eprintln!("Error encoding frame: {}", e);
+                            continue;
+                        }
+                    };
+                    match socket.write_message(message) {
+                        Ok(_) => {
+                            thread::sleep(time::Duration::from_millis(1000));
+                        }
+                        Err(_) => {
+                            eprintln!("Error sending message. Attempting to reconnect...");
+                            break;
+                        }
                     }
                 }
-            },
+            }
             Err(e) => {
                 eprintln!("Error connecting to server: {}", e);
                 thread::sleep(time::Duration::from_millis(5000));